@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+
+use crate::coord::Coord;
+use crate::direction::Connectivity;
+use crate::entry::Entry;
+use crate::graphmat::GraphMat;
+
+// Only for 3D matrices, since neighbours are found through `Direction`
+impl<T, C: Coord> GraphMat<T, C> {
+    /**
+     * @returns The cells reachable from `seed` through neighbouring cells whose data satisfies `pred`, in breadth-first order
+     *
+     * Empty if `seed` itself has no data, or doesn't satisfy `pred`
+     */
+    pub fn flood_fill<UnaryPredicate>(
+        &self,
+        seed: [C; 3],
+        connectivity: Connectivity,
+        pred: UnaryPredicate,
+    ) -> Vec<[C; 3]>
+    where
+        UnaryPredicate: Fn(&T) -> bool,
+    {
+        let mut visited = GraphMat::with_block_size(self.block_size());
+        self.fill(seed, connectivity, &pred, &mut visited, ())
+    }
+
+    /**
+     * @brief Groups the cells having some data into bodies, two cells being in the same body if they can be reached
+     * from each other through neighbouring cells having some data
+     *
+     * @returns The cells of each body, in no particular order
+     */
+    pub fn connected_components(&self, connectivity: Connectivity) -> Vec<Vec<[C; 3]>> {
+        self.components(connectivity).0
+    }
+
+    /**
+     * @brief Same as connected_components(), but as a matrix having the index of the body of each cell in it
+     */
+    pub fn label_components(&self, connectivity: Connectivity) -> GraphMat<usize, C> {
+        self.components(connectivity).1
+    }
+
+    fn components(&self, connectivity: Connectivity) -> (Vec<Vec<[C; 3]>>, GraphMat<usize, C>) {
+        let mut components = Vec::new();
+        let mut labels = GraphMat::with_block_size(self.block_size());
+
+        for coord in self.keys() {
+            if labels.get(coord).is_none() {
+                let component = self.fill(coord, connectivity, &|_: &T| true, &mut labels, components.len());
+                components.push(component);
+            }
+        }
+
+        (components, labels)
+    }
+
+    /**
+     * @brief Breadth-first search from `seed`, setting `label` in `visited` for each cell reached
+     *
+     * Cells already in `visited` are not reached again, so memory only grows with the number of cells reached
+     */
+    fn fill<L: Copy, UnaryPredicate>(
+        &self,
+        seed: [C; 3],
+        connectivity: Connectivity,
+        pred: &UnaryPredicate,
+        visited: &mut GraphMat<L, C>,
+        label: L,
+    ) -> Vec<[C; 3]>
+    where
+        UnaryPredicate: Fn(&T) -> bool,
+    {
+        let mut region = Vec::new();
+        if !self.get(seed).is_some_and(pred) || visited.get(seed).is_some() {
+            return region;
+        }
+
+        let mut queue = VecDeque::from([seed]);
+        visited.set(seed, label);
+
+        while let Some(coord) = queue.pop_front() {
+            region.push(coord);
+
+            for (_, neighbour, data) in self.neighbours(coord, connectivity) {
+                if !pred(data) {
+                    continue;
+                }
+                if let Entry::Vacant(entry) = visited.entry(neighbour) {
+                    entry.insert(label);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        region
+    }
+}
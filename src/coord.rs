@@ -0,0 +1,48 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/**
+ * @brief Scalar type of a coordinate, GraphMat<T, C, D> indexes cells by [C; D]
+ *
+ * Arithmetic on coordinates is done in i64, which can represent every value of all the implementing types,
+ * so leaders stay correct for negative values too, whatever the width
+ */
+pub trait Coord: Copy + Eq + Ord + Hash + Debug {
+    const MIN: Self;
+    const MAX: Self;
+
+    fn to_i64(self) -> i64;
+
+    /**
+     * @returns None if `value` can't be represented by this type, for eg. -1 for u32
+     */
+    fn from_i64(value: i64) -> Option<Self>;
+
+    /**
+     * @returns self + delta, None if that overflows this type
+     */
+    fn offset(self, delta: i64) -> Option<Self> {
+        Self::from_i64(self.to_i64().checked_add(delta)?)
+    }
+}
+
+macro_rules! impl_coord {
+    ($($t:ty),*) => {
+        $(
+            impl Coord for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn to_i64(self) -> i64 {
+                    self as i64
+                }
+
+                fn from_i64(value: i64) -> Option<Self> {
+                    <$t>::try_from(value).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_coord!(i8, i16, i32, i64, u8, u16, u32);
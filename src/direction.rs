@@ -1,7 +1,10 @@
+use crate::node::{EAST, NORTH, SKY};
+
 #[allow(non_camel_case_types)]
 #[allow(unused)]
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "nightly", derive(std::marker::ConstParamTy))]
 pub enum Direction {
     uttar,   // north
     purva,   // east
@@ -15,4 +18,347 @@ pub enum Direction {
 
     urdhwa,     // Akash (skywards)
     adharastha, // Patal (downwards)
+
+    // One step up, alongwith one of the planar directions
+    urdhwa_uttar,
+    urdhwa_purva,
+    urdhwa_paschim,
+    urdhwa_dakshin,
+    urdhwa_vayavya,
+    urdhwa_ishanya,
+    urdhwa_nairutya,
+    urdhwa_agneya,
+
+    // One step down, alongwith one of the planar directions
+    adharastha_uttar,
+    adharastha_purva,
+    adharastha_paschim,
+    adharastha_dakshin,
+    adharastha_vayavya,
+    adharastha_ishanya,
+    adharastha_nairutya,
+    adharastha_agneya,
+}
+
+// English names, for eg. Direction::UP_NORTH_EAST is Direction::urdhwa_ishanya
+impl Direction {
+    pub const NORTH: Direction = Direction::uttar;
+    pub const EAST: Direction = Direction::purva;
+    pub const WEST: Direction = Direction::paschim;
+    pub const SOUTH: Direction = Direction::dakshin;
+    pub const NORTH_WEST: Direction = Direction::vayavya;
+    pub const NORTH_EAST: Direction = Direction::ishanya;
+    pub const SOUTH_WEST: Direction = Direction::nairutya;
+    pub const SOUTH_EAST: Direction = Direction::agneya;
+
+    pub const UP: Direction = Direction::urdhwa;
+    pub const DOWN: Direction = Direction::adharastha;
+
+    pub const UP_NORTH: Direction = Direction::urdhwa_uttar;
+    pub const UP_EAST: Direction = Direction::urdhwa_purva;
+    pub const UP_WEST: Direction = Direction::urdhwa_paschim;
+    pub const UP_SOUTH: Direction = Direction::urdhwa_dakshin;
+    pub const UP_NORTH_WEST: Direction = Direction::urdhwa_vayavya;
+    pub const UP_NORTH_EAST: Direction = Direction::urdhwa_ishanya;
+    pub const UP_SOUTH_WEST: Direction = Direction::urdhwa_nairutya;
+    pub const UP_SOUTH_EAST: Direction = Direction::urdhwa_agneya;
+
+    pub const DOWN_NORTH: Direction = Direction::adharastha_uttar;
+    pub const DOWN_EAST: Direction = Direction::adharastha_purva;
+    pub const DOWN_WEST: Direction = Direction::adharastha_paschim;
+    pub const DOWN_SOUTH: Direction = Direction::adharastha_dakshin;
+    pub const DOWN_NORTH_WEST: Direction = Direction::adharastha_vayavya;
+    pub const DOWN_NORTH_EAST: Direction = Direction::adharastha_ishanya;
+    pub const DOWN_SOUTH_WEST: Direction = Direction::adharastha_nairutya;
+    pub const DOWN_SOUTH_EAST: Direction = Direction::adharastha_agneya;
+}
+
+// All the directions, in the order they are declared
+pub(crate) const ALL: [Direction; 26] = [
+    Direction::uttar,
+    Direction::purva,
+    Direction::paschim,
+    Direction::dakshin,
+    Direction::vayavya,
+    Direction::ishanya,
+    Direction::nairutya,
+    Direction::agneya,
+    Direction::urdhwa,
+    Direction::adharastha,
+    Direction::urdhwa_uttar,
+    Direction::urdhwa_purva,
+    Direction::urdhwa_paschim,
+    Direction::urdhwa_dakshin,
+    Direction::urdhwa_vayavya,
+    Direction::urdhwa_ishanya,
+    Direction::urdhwa_nairutya,
+    Direction::urdhwa_agneya,
+    Direction::adharastha_uttar,
+    Direction::adharastha_purva,
+    Direction::adharastha_paschim,
+    Direction::adharastha_dakshin,
+    Direction::adharastha_vayavya,
+    Direction::adharastha_ishanya,
+    Direction::adharastha_nairutya,
+    Direction::adharastha_agneya,
+];
+
+impl Direction {
+    /**
+     * @returns Change in the [x, y, z] coordinate on moving one step in this direction, each being -1, 0 or 1
+     */
+    pub fn offset(&self) -> [i32; 3] {
+        use Direction::*;
+
+        // Planar part of the direction, and the step along z
+        let ((x, y), z) = match self {
+            uttar => ((0, 1), 0),
+            purva => ((1, 0), 0),
+            paschim => ((-1, 0), 0),
+            dakshin => ((0, -1), 0),
+            vayavya => ((-1, 1), 0),
+            ishanya => ((1, 1), 0),
+            nairutya => ((-1, -1), 0),
+            agneya => ((1, -1), 0),
+
+            urdhwa => ((0, 0), 1),
+            adharastha => ((0, 0), -1),
+
+            urdhwa_uttar => ((0, 1), 1),
+            urdhwa_purva => ((1, 0), 1),
+            urdhwa_paschim => ((-1, 0), 1),
+            urdhwa_dakshin => ((0, -1), 1),
+            urdhwa_vayavya => ((-1, 1), 1),
+            urdhwa_ishanya => ((1, 1), 1),
+            urdhwa_nairutya => ((-1, -1), 1),
+            urdhwa_agneya => ((1, -1), 1),
+
+            adharastha_uttar => ((0, 1), -1),
+            adharastha_purva => ((1, 0), -1),
+            adharastha_paschim => ((-1, 0), -1),
+            adharastha_dakshin => ((0, -1), -1),
+            adharastha_vayavya => ((-1, 1), -1),
+            adharastha_ishanya => ((1, 1), -1),
+            adharastha_nairutya => ((-1, -1), -1),
+            adharastha_agneya => ((1, -1), -1),
+        };
+
+        [x, y, z]
+    }
+
+    /**
+     * @returns The direction whose offset() is `offset`, None for [0, 0, 0] or if any of them is not -1, 0 or 1
+     */
+    pub fn from_offset(offset: [i32; 3]) -> Option<Direction> {
+        ALL.into_iter().find(|dir| dir.offset() == offset)
+    }
+
+    pub fn opposite(&self) -> Direction {
+        // SAFETY: Negating an offset of a direction is also an offset of some direction
+        Direction::from_offset(self.offset().map(|c| -c)).unwrap()
+    }
+
+    /**
+     * @brief All the 26 directions to the neighbours of a cell
+     */
+    pub fn all() -> impl Iterator<Item = Direction> {
+        ALL.into_iter()
+    }
+
+    /**
+     * @brief The 6 directions to the neighbours sharing a face, ie. differing along only one axis
+     */
+    pub fn face() -> impl Iterator<Item = Direction> {
+        Direction::all().filter(|dir| dir.axes_moved() == 1)
+    }
+
+    /**
+     * @brief The 12 directions to the neighbours sharing only an edge, ie. differing along two axes
+     */
+    pub fn edge() -> impl Iterator<Item = Direction> {
+        Direction::all().filter(|dir| dir.axes_moved() == 2)
+    }
+
+    /**
+     * @brief The 8 directions to the neighbours sharing only a corner, ie. differing along all three axes
+     */
+    pub fn corner() -> impl Iterator<Item = Direction> {
+        Direction::all().filter(|dir| dir.axes_moved() == 3)
+    }
+
+    // Number of axes along which the coordinate changes on moving in this direction
+    pub(crate) fn axes_moved(&self) -> usize {
+        self.offset().iter().filter(|c| **c != 0).count()
+    }
+}
+
+/**
+ * @brief One of the axes of a 3D matrix, the coordinate is [x, y, z]
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    // Index of this axis in a coordinate
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            Axis::X => EAST,
+            Axis::Y => NORTH,
+            Axis::Z => SKY,
+        }
+    }
+
+    // The other two axes, in order, ie. the axes of a plane perpendicular to this one
+    pub(crate) fn others(&self) -> [usize; 2] {
+        match self {
+            Axis::X => [NORTH, SKY],
+            Axis::Y => [EAST, SKY],
+            Axis::Z => [EAST, NORTH],
+        }
+    }
+}
+
+/**
+ * @brief One of the two ends of an axis, for eg. Side::Max along Axis::Z is the top
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Min,
+    Max,
+}
+
+/**
+ * @brief Which of the cells around a cell count as its neighbours
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    // Sharing a face, ie. Direction::face()
+    Six,
+    // Sharing a face or an edge
+    Eighteen,
+    // Sharing a face, an edge or a corner, ie. Direction::all()
+    TwentySix,
+}
+
+impl Connectivity {
+    pub fn includes(&self, dir: &Direction) -> bool {
+        let max_axes_moved = match self {
+            Connectivity::Six => 1,
+            Connectivity::Eighteen => 2,
+            Connectivity::TwentySix => 3,
+        };
+
+        dir.axes_moved() <= max_axes_moved
+    }
+
+    pub fn directions(&self) -> impl Iterator<Item = Direction> {
+        let connectivity = *self;
+        Direction::all().filter(move |dir| connectivity.includes(dir))
+    }
+}
+
+/**
+ * @brief A direction known at compile time, used as GraphMat::iter_dir::<markers::Purva>()
+ *
+ * Implemented by the zero sized types in `markers`, one per Direction
+ */
+pub trait DirectionMarker {
+    const DIRECTION: Direction;
+}
+
+macro_rules! direction_markers {
+    ($($marker:ident => $dir:ident),* $(,)?) => {
+        $(
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+            pub struct $marker;
+
+            impl DirectionMarker for $marker {
+                const DIRECTION: Direction = Direction::$dir;
+            }
+        )*
+    };
+}
+
+pub mod markers {
+    use super::{Direction, DirectionMarker};
+
+    direction_markers! {
+        Uttar => uttar,
+        Purva => purva,
+        Paschim => paschim,
+        Dakshin => dakshin,
+
+        Vayavya => vayavya,
+        Ishanya => ishanya,
+        Nairutya => nairutya,
+        Agneya => agneya,
+
+        Urdhwa => urdhwa,
+        Adharastha => adharastha,
+
+        UrdhwaUttar => urdhwa_uttar,
+        UrdhwaPurva => urdhwa_purva,
+        UrdhwaPaschim => urdhwa_paschim,
+        UrdhwaDakshin => urdhwa_dakshin,
+        UrdhwaVayavya => urdhwa_vayavya,
+        UrdhwaIshanya => urdhwa_ishanya,
+        UrdhwaNairutya => urdhwa_nairutya,
+        UrdhwaAgneya => urdhwa_agneya,
+
+        AdharasthaUttar => adharastha_uttar,
+        AdharasthaPurva => adharastha_purva,
+        AdharasthaPaschim => adharastha_paschim,
+        AdharasthaDakshin => adharastha_dakshin,
+        AdharasthaVayavya => adharastha_vayavya,
+        AdharasthaIshanya => adharastha_ishanya,
+        AdharasthaNairutya => adharastha_nairutya,
+        AdharasthaAgneya => adharastha_agneya,
+    }
+
+    // English names, for eg. markers::UpNorthEast is markers::UrdhwaIshanya
+    pub type North = Uttar;
+    pub type East = Purva;
+    pub type West = Paschim;
+    pub type South = Dakshin;
+    pub type NorthWest = Vayavya;
+    pub type NorthEast = Ishanya;
+    pub type SouthWest = Nairutya;
+    pub type SouthEast = Agneya;
+
+    pub type Up = Urdhwa;
+    pub type Down = Adharastha;
+
+    pub type UpNorth = UrdhwaUttar;
+    pub type UpEast = UrdhwaPurva;
+    pub type UpWest = UrdhwaPaschim;
+    pub type UpSouth = UrdhwaDakshin;
+    pub type UpNorthWest = UrdhwaVayavya;
+    pub type UpNorthEast = UrdhwaIshanya;
+    pub type UpSouthWest = UrdhwaNairutya;
+    pub type UpSouthEast = UrdhwaAgneya;
+
+    pub type DownNorth = AdharasthaUttar;
+    pub type DownEast = AdharasthaPurva;
+    pub type DownWest = AdharasthaPaschim;
+    pub type DownSouth = AdharasthaDakshin;
+    pub type DownNorthWest = AdharasthaVayavya;
+    pub type DownNorthEast = AdharasthaIshanya;
+    pub type DownSouthWest = AdharasthaNairutya;
+    pub type DownSouthEast = AdharasthaAgneya;
+
+    /**
+     * @brief Any Direction as a marker, for eg. ConstDir<{ Direction::purva }>, needs the `nightly` feature
+     */
+    #[cfg(feature = "nightly")]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct ConstDir<const DIR: Direction>;
+
+    #[cfg(feature = "nightly")]
+    impl<const DIR: Direction> DirectionMarker for ConstDir<DIR> {
+        const DIRECTION: Direction = DIR;
+    }
 }
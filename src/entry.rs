@@ -0,0 +1,121 @@
+use generational_arena::Index as IndexInArena;
+
+use crate::coord::Coord;
+use crate::graphmat::{GraphMat, Walk};
+
+/**
+ * @brief A view into a single cell of a GraphMat, which may either be vacant or occupied
+ *
+ * Constructed by GraphMat::entry()
+ */
+pub enum Entry<'a, T, C = i32, const D: usize = 3> {
+    Occupied(OccupiedEntry<'a, T, C, D>),
+    Vacant(VacantEntry<'a, T, C, D>),
+}
+
+pub struct OccupiedEntry<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) graphmat: &'a mut GraphMat<T, C, D>,
+    pub(crate) coord: [C; D],
+    pub(crate) index: IndexInArena,
+}
+
+pub struct VacantEntry<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) graphmat: &'a mut GraphMat<T, C, D>,
+    pub(crate) coord: [C; D],
+    // Where GraphMat::entry() stopped walking, so that insert() can continue from there
+    pub(crate) walk: Walk,
+}
+
+impl<'a, T, C: Coord, const D: usize> Entry<'a, T, C, D> {
+    pub fn coord(&self) -> [C; D] {
+        match self {
+            Entry::Occupied(entry) => entry.coord,
+            Entry::Vacant(entry) => entry.coord,
+        }
+    }
+
+    pub fn or_insert(self, default: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut T
+    where
+        F: FnOnce() -> T,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut T
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+
+    /**
+     * @brief Calls `f` with the value, only if the cell is occupied
+     */
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut T),
+    {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> OccupiedEntry<'a, T, C, D> {
+    pub fn coord(&self) -> [C; D] {
+        self.coord
+    }
+
+    pub fn get(&self) -> &T {
+        // SAFETY: An OccupiedEntry is only created for an allocated node having some data
+        self.graphmat.arena.get(self.index).unwrap().get().unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        // SAFETY: An OccupiedEntry is only created for an allocated node having some data
+        self.graphmat.arena.get_mut(self.index).unwrap().get_mut().unwrap()
+    }
+
+    pub fn into_mut(self) -> &'a mut T {
+        // SAFETY: An OccupiedEntry is only created for an allocated node having some data
+        self.graphmat.arena.get_mut(self.index).unwrap().get_mut().unwrap()
+    }
+
+    /**
+     * @returns The value that was previously stored in the cell
+     */
+    pub fn insert(&mut self, value: T) -> T {
+        // SAFETY: An OccupiedEntry is only created for an allocated node having some data
+        self.graphmat.arena.get_mut(self.index).unwrap().set(value).unwrap()
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> VacantEntry<'a, T, C, D> {
+    pub fn coord(&self) -> [C; D] {
+        self.coord
+    }
+
+    pub fn insert(self, value: T) -> &'a mut T {
+        let index = self.graphmat.complete_walk(self.coord, self.walk);
+        self.graphmat.cell_occupied(self.coord);
+
+        // SAFETY: complete_walk returns an index allocated in self.arena
+        let node = self.graphmat.arena.get_mut(index).unwrap();
+        node.set(value);
+
+        // SAFETY: The value was set just above
+        node.get_mut().unwrap()
+    }
+}
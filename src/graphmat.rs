@@ -1,119 +1,229 @@
-use crate::iterators::{GraphMatFreeIterator, GraphMatIterator};
-use crate::{direction::Direction, node::Node};
+use crate::coord::Coord;
+use crate::entry::{Entry, OccupiedEntry, VacantEntry};
+use crate::iterators::{
+    GraphMatFreeIterator, GraphMatFreeRefIterator, GraphMatIterator, GraphMatRefIterator, IntoIter,
+    Iter, IterMut, Keys, Neighbours, Region, RegionMut, RegionWalk, Values, ValuesMut,
+};
+use crate::node::{step_along, EAST, NORTH, SKY};
+use crate::direction::{Connectivity, Direction, DirectionMarker, ALL};
+use crate::metric::{Ball, Metric};
+use crate::node::Node;
 use generational_arena::{Arena, Index as IndexInArena};
 use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+use std::sync::OnceLock;
+
+/**
+ * @brief Sparse matrix of `D` dimensions, indexed by [C; D]
+ *
+ * Defaults to 3D, use GraphMat2D/GraphMat4D (or any other `D`) for other dimensions
+ */
+#[derive(Clone)]
+pub struct GraphMat<T, C = i32, const D: usize = 3> {
+    pub(crate) arena: Arena<Node<T, C, D>>,
+    pub(crate) map: HashMap<[C; D], IndexInArena>,
+    // Number of nodes having some data, ie. not counting placeholder nodes
+    pub(crate) len: usize,
+    // Edge length of the block of cells reached through a leader, ie. a leader covers block_size^D cells
+    block_size: i64,
+    // Inclusive (min, max) of the cells having some data, grown on every insertion
+    // After removing a cell on the boundary it may be too large, so it is cleared and recomputed by the next bounds()
+    // A OnceLock, so that the matrix stays Sync and can be shared between threads
+    bounds: OnceLock<Option<([C; D], [C; D])>>,
+}
+
+pub type GraphMat2D<T, C = i32> = GraphMat<T, C, 2>;
+pub type GraphMat4D<T, C = i32> = GraphMat<T, C, 4>;
+
+// Result of walking from the leader towards a coordinate, see `GraphMat::walk`
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Walk {
+    // A node (maybe only a placeholder, ie. with no data) is allocated at the coordinate
+    Found(IndexInArena),
+    // The walk stopped after `steps_done` links, at `deepest` (None if even the leader is not allocated)
+    Missing {
+        deepest: Option<IndexInArena>,
+        steps_done: usize,
+    },
+}
 
-pub struct GraphMat<T> {
-    pub(crate) arena: Arena<Node<T>>,
-    map: HashMap<(i32, i32, i32), IndexInArena>,
+/**
+ * @brief The axes along which links are followed from the leader to reach `coord`, alongwith the coordinate reached after each step
+ *
+ * Nodes are chained along the axes in order, for eg. in 3D with a block size of 2, leader + (1,1,1) is leader.east.north.sky,
+ * and with a block size of 4, leader + (3,0,2) is leader.east.east.east.sky.sky
+ */
+fn links_from_leader<C: Coord, const D: usize>(
+    leader_coord: [C; D],
+    coord: [C; D],
+    block_size: i64,
+) -> impl Iterator<Item = (usize, [C; D])> {
+    let mut diff = [0; D];
+    for axis in 0..D {
+        diff[axis] = coord[axis].to_i64() - leader_coord[axis].to_i64();
+    }
+    if !diff.iter().all(|d| (0..block_size).contains(d)) {
+        panic!(
+            "Invalid direction, coord {:?} is probably not a neighbour of {:?}",
+            coord, leader_coord
+        );
+    }
+
+    let mut pos = leader_coord;
+    (0..D)
+        .flat_map(move |axis| std::iter::repeat_n(axis, diff[axis] as usize))
+        .map(move |axis| {
+            // SAFETY: `coord` itself is a valid [C; D], so every position between it and its leader also is
+            pos = step_along(pos, axis).unwrap();
+            (axis, pos)
+        })
+}
+
+// Smallest box containing both `bounds` and `coord`
+fn grow_bounds<C: Coord, const D: usize>(bounds: Option<([C; D], [C; D])>, coord: [C; D]) -> ([C; D], [C; D]) {
+    match bounds {
+        None => (coord, coord),
+        Some((mut min, mut max)) => {
+            for axis in 0..D {
+                min[axis] = min[axis].min(coord[axis]);
+                max[axis] = max[axis].max(coord[axis]);
+            }
+            (min, max)
+        }
+    }
 }
 
-impl<T> GraphMat<T> {
+impl<T, C: Coord, const D: usize> GraphMat<T, C, D> {
     pub fn new() -> Self {
+        GraphMat::with_block_size(2)
+    }
+
+    /**
+     * @brief Creates a matrix where each leader covers a block of block_size^D cells, instead of the default 2^D
+     *
+     * Larger blocks mean fewer leaders to hash, but longer chains of links to follow from a leader
+     * Blocks start from the smallest value of C along each axis, so every value of C is covered whatever the block size
+     */
+    pub fn with_block_size(block_size: usize) -> Self {
+        assert!(block_size >= 1, "Block size must be at least 1");
+        let Ok(block_size) = i64::try_from(block_size) else {
+            panic!("Block size must be at most i64::MAX, got {}", block_size);
+        };
+
         GraphMat {
             arena: Arena::new(),
             map: HashMap::new(),
+            len: 0,
+            block_size,
+            bounds: OnceLock::from(None),
         }
     }
 
-    // Note: This function does NOT modify `self.map`, do it in the other functions
-    fn allocate_one_node(&mut self, data: T, coord: (i32, i32, i32)) ->IndexInArena{
-        self.arena.insert(Node::new(Some(data), coord))
+    pub fn block_size(&self) -> usize {
+        self.block_size as usize
+    }
+
+    pub(crate) fn leader_coord(&self, coord: [C; D]) -> [C; D] {
+        // Blocks are counted from C::MIN, so every leader is in range of C, only the last block along an axis may be cut
+        // short by C::MAX (see block_end())
+        // Rounding down (not towards 0), so that for eg. the leader of -3 is -4 (with a block size of 2)
+        // Computed in i128, since for C = i64 the distance from i64::MIN may not fit an i64
+        coord.map(|c| {
+            let from_min = c.to_i64() as i128 - C::MIN.to_i64() as i128;
+            let leader = C::MIN.to_i64() as i128 + from_min / self.block_size as i128 * self.block_size as i128;
+
+            // SAFETY: The leader lies between C::MIN and `c`
+            C::from_i64(leader as i64).unwrap()
+        })
+    }
+
+    // Last cell of the block of `leader_coord`, clamped to the range of C
+    pub(crate) fn block_end(&self, leader_coord: [C; D]) -> [C; D] {
+        leader_coord.map(|c| c.offset(self.block_size - 1).unwrap_or(C::MAX))
+    }
+
+    // Links to follow from the leader of `coord` to reach it
+    fn links_from_leader(&self, coord: [C; D]) -> impl Iterator<Item = (usize, [C; D])> {
+        links_from_leader(self.leader_coord(coord), coord, self.block_size)
     }
 
     // Note: This function does NOT modify `self.map`, do it in the other functions
-    fn default_allocate_one_node(&mut self, coord: (i32, i32, i32)) ->IndexInArena{
+    fn default_allocate_one_node(&mut self, coord: [C; D]) ->IndexInArena{
         self.arena.insert(Node::new(None, coord))
     }
 
-    pub fn get_node_index(&self, coord: (i32, i32, i32)) -> Option<IndexInArena> {
-        // let leader_coord = ((coord.0 / 2) * 2, (coord.1 / 2) * 2, (coord.2 / 2) * 2);
-        let leader_coord = (
-            if coord.0 % 2 == 0 { coord.0 } else { coord.0 - 1 },
-            if coord.1 % 2 == 0 { coord.1 } else { coord.1 - 1 },
-            if coord.2 % 2 == 0 { coord.2 } else { coord.2 - 1 }
-        );
+    /**
+     * @brief Follows the leader's links towards `coord`, as far as they are allocated
+     */
+    pub(crate) fn walk(&self, coord: [C; D]) -> Walk {
+        match self.map.get(&self.leader_coord(coord)) {
+            None => Walk::Missing { deepest: None, steps_done: 0 },
+            Some(leader) => self.walk_from_leader(*leader, coord),
+        }
+    }
 
-        if coord == leader_coord {
-            return match self.map.get(&coord) { None => None, Some(idx) => Some(*idx) };
+    // Same as walk(), when the index of the leader of `coord` is already known
+    pub(crate) fn walk_from_leader(&self, leader: IndexInArena, coord: [C; D]) -> Walk {
+        let mut curr = leader;
+
+        for (steps_done, (axis, _)) in self.links_from_leader(coord).enumerate() {
+            // SAFETY: If self.map or a node refers to an index, it must be allocated in self.arena
+            match self.arena.get(curr).unwrap().links[axis] {
+                None => {
+                    return Walk::Missing { deepest: Some(curr), steps_done };
+                }
+                Some(idx) => curr = idx,
+            }
         }
 
-        let leader = match self.map.get(&leader_coord) {
-            None => {
-                return None;
+        Walk::Found(curr)
+    }
+
+    /**
+     * @brief Allocates the nodes (and the leader if required) that `walk` could not find, the last one being at `coord`
+     *
+     * @returns Index of the node at `coord`, which is a placeholder (ie. has no data) if it was just allocated
+     */
+    pub(crate) fn complete_walk(&mut self, coord: [C; D], walk: Walk) -> IndexInArena {
+        let (deepest, steps_done) = match walk {
+            Walk::Found(idx) => {
+                return idx;
             }
-            Some(idx) => {
-                // SAFETY: If self.map says this index refers to a node, it must be allocated in self.arena
-                self.arena.get(*idx).unwrap()
+            Walk::Missing { deepest, steps_done } => (deepest, steps_done),
+        };
+
+        let leader_coord = self.leader_coord(coord);
+        let mut curr = match deepest {
+            Some(idx) => idx,
+            None => {
+                // Even if this is not the location that is to be set, this is still required according to current design
+                let index = self.default_allocate_one_node(leader_coord);
+                self.map.insert(leader_coord, index);
+                index
             }
         };
 
-        let diff = (
-            coord.0 - leader_coord.0,
-            coord.1 - leader_coord.1,
-            coord.2 - leader_coord.2,
-        );
-        match diff {
-            (1, 0, 0) => leader.east,
-            (0, 1, 0) => leader.north,
-            (0, 0, 1) => leader.sky,
-            (0, 1, 1) => {
-                // north-sky
-                match leader.north {
-                    None => None,
-                    Some(idx) => {
-                        // SAFETY: If a node says this index refers to a neighbour node, it must be allocated in self.arena
-                        self.arena.get(idx).unwrap().sky
-                    }
-                }
-            },
-            (1, 0, 1) => {
-                // east-sky
-                match leader.east {
-                    None => None,
-                    Some(idx) => {
-                        // SAFETY: If a node says this index refers to a neighbour node, it must be allocated in self.arena
-                        self.arena.get(idx).unwrap().sky
-                    }
-                }
-            },
-            (1, 1, 0) => {
-                // north-east
-                match leader.north {
-                    None => None,
-                    Some(idx) => {
-                        // SAFETY: If a node says this index refers to a neighbour node, it must be allocated in self.arena
-                        self.arena.get(idx).unwrap().east
-                    }
-                }
-            },
-            (1, 1, 1) => {
-                // north-east-sky
-                match leader.north {
-                    None => None,
-                    Some(idx) => {
-                        // SAFETY: If a node says this index refers to a neighbour node, it must be allocated in self.arena
-                        match self.arena.get(idx).unwrap().east {
-                            None => None,
-                            Some(idx) => {
-                                // SAFETY: If a node says this index refers to a neighbour node, it must be allocated in self.arena
-                                self.arena.get(idx).unwrap().sky
-                            }
-                        }
-                    }
-                }
-            },
+        for (axis, pos) in self.links_from_leader(coord).skip(steps_done) {
+            let index = self.default_allocate_one_node(pos);
 
-            _ => {
-                panic!(
-                    "Invalid direction, coord {:?} is probably not a neighbour of {:?}",
-                    coord, leader_coord
-                );
-            }
+            // SAFETY: `curr` is either the leader from self.map, or was allocated in the previous iteration
+            self.arena.get_mut(curr).unwrap().links[axis].replace(index);
+            curr = index;
+        }
+
+        curr
+    }
+
+    pub fn get_node_index(&self, coord: [C; D]) -> Option<IndexInArena> {
+        match self.walk(coord) {
+            Walk::Found(idx) => Some(idx),
+            Walk::Missing { .. } => None,
         }
     }
 
-    pub fn get<'a>(&'a self, coord: (i32, i32, i32)) -> Option<&'a T> {
+    pub fn get(&self, coord: [C; D]) -> Option<&T> {
         match self.get_node_index(coord) {
             None => None,
             // SAFETY: get_node_index returned an index to the node, so self.arena must have it
@@ -121,7 +231,7 @@ impl<T> GraphMat<T> {
         }
     }
 
-    pub fn get_mut<'a>(&'a mut self, coord: (i32, i32, i32)) -> Option<&'a mut T> {
+    pub fn get_mut(&mut self, coord: [C; D]) -> Option<&mut T> {
          match self.get_node_index(coord) {
             None => None,
             // SAFETY: get_node_index returned an index to the node, so self.arena must have it
@@ -129,380 +239,708 @@ impl<T> GraphMat<T> {
         }
     }
 
-    pub fn set(&mut self, coord: (i32, i32, i32), data: T) {
-        let leader_coord = (
-            if coord.0 % 2 == 0 { coord.0 } else { coord.0 - 1 },
-            if coord.1 % 2 == 0 { coord.1 } else { coord.1 - 1 },
-            if coord.2 % 2 == 0 { coord.2 } else { coord.2 - 1 }
-        );
-        // !(maybe unncecessary) Optimisation "for unsigned indices", same result as above (REQUIRED: index is unsigned)
-        // let leader_coord = ((coord.0 / 2) * 2, (coord.1 / 2) * 2, (coord.2 / 2) * 2);
+    pub fn set(&mut self, coord: [C; D], data: T) {
+        let walk = self.walk(coord);
+        let idx = self.complete_walk(coord, walk);
 
-        if coord == leader_coord {
-            match self.map.get(&leader_coord) {
-                None => {
-                    let index = self.allocate_one_node(data, leader_coord);
-                    self.map.insert(leader_coord, index);
-                }
-                Some(idx) => {
-                    // SAFETY: Since self.map.get() is NOT None, that means there is a node at that coord,
-                    // and hence the corresponding index MUST be present in arena
-                    self.arena.get_mut(*idx).unwrap().set(data);
-                }
+        self.set_node(idx, data);
+    }
+
+    // Sets the data of an allocated node, keeping self.len and the bounds updated
+    fn set_node(&mut self, idx: IndexInArena, data: T) {
+        // SAFETY: Caller must only pass indices allocated in self.arena
+        let node = self.arena.get_mut(idx).unwrap();
+        if node.set(data).is_none() {
+            let coord = node.coord;
+            self.cell_occupied(coord);
+        }
+    }
+
+    // To be called whenever an empty cell gets some data
+    pub(crate) fn cell_occupied(&mut self, coord: [C; D]) {
+        self.len += 1;
+
+        // Nothing to grow if the bounds are to be recomputed anyways
+        if let Some(bounds) = self.bounds.get_mut() {
+            *bounds = Some(grow_bounds(*bounds, coord));
+        }
+    }
+
+    // To be called whenever the data is taken out of a cell
+    fn cell_vacated(&mut self, coord: [C; D]) {
+        self.len -= 1;
+
+        if self.len == 0 {
+            self.bounds = OnceLock::from(None);
+        } else if let Some(Some((min, max))) = self.bounds.get() {
+            // The bounds can only shrink if the removed cell was lying on them
+            if (0..D).any(|axis| coord[axis] == min[axis] || coord[axis] == max[axis]) {
+                self.bounds.take();
             }
+        }
+    }
+
+    /**
+     * @brief Gets the cell at `coord` for in-place manipulation, inserting only if required
+     *
+     * The leader and its links are walked only once, even if a value is inserted afterwards
+     */
+    pub fn entry(&mut self, coord: [C; D]) -> Entry<'_, T, C, D> {
+        let walk = self.walk(coord);
+
+        match walk {
+            // SAFETY: walk returned an index to the node, so self.arena must have it
+            Walk::Found(idx) if self.arena.get(idx).unwrap().get().is_some() => {
+                Entry::Occupied(OccupiedEntry {
+                    graphmat: self,
+                    coord,
+                    index: idx,
+                })
+            }
+            _ => Entry::Vacant(VacantEntry {
+                graphmat: self,
+                coord,
+                walk,
+            }),
+        }
+    }
+
+    /**
+     * @brief Iterates over all cells having some data, in no particular order
+     */
+    pub fn iter(&self) -> Iter<'_, T, C, D> {
+        Iter {
+            inner: self.arena.iter(),
+            remaining: self.len,
+        }
+    }
 
-            return;
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, C, D> {
+        IterMut {
+            inner: self.arena.iter_mut(),
+            remaining: self.len,
         }
+    }
+
+    pub fn keys(&self) -> Keys<'_, T, C, D> {
+        Keys { inner: self.iter() }
+    }
 
-        // Invariant: By now, it's establised that coord != cube_center
+    pub fn values(&self) -> Values<'_, T, C, D> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, T, C, D> {
+        ValuesMut {
+            inner: self.iter_mut(),
+        }
+    }
+
+    /**
+     * @brief Iterates over the cells having some data inside the box from `min` to `max` (both inclusive), in no particular order
+     *
+     * Only the leaders overlapping the box are visited, so this costs about the number of occupied cells there, not the volume of the box
+     */
+    pub fn iter_region(&self, min: [C; D], max: [C; D]) -> Region<'_, T, C, D> {
+        Region {
+            walk: self.region_walk(min, max, None),
+            graphmat: self,
+        }
+    }
 
-        if self.map.get(&leader_coord).is_none() {
-            // Even if this is not the location that is to be set, this is still required according to current design
-            let index = self.default_allocate_one_node(leader_coord);
-            self.map.insert(leader_coord, index);
+    /**
+     * @brief Same as iter_region(), but the values can be modified, through next_mut() or for_each_mut()
+     */
+    pub fn iter_region_mut(&mut self, min: [C; D], max: [C; D]) -> RegionMut<'_, T, C, D> {
+        RegionMut {
+            walk: self.region_walk(min, max, None),
+            graphmat: self,
         }
+    }
+
+    /**
+     * @brief Iterates over the cells having some data within a Euclidean distance of `radius` from `center`, in no particular order
+     *
+     * Like iter_region(), only the leaders whose block overlaps the sphere are visited
+     */
+    pub fn within_radius(&self, center: [C; D], radius: f64) -> Region<'_, T, C, D> {
+        self.within(Ball { center, radius, metric: Metric::Euclidean })
+    }
 
-        // ![START] Allocating some nodes in advance
+    /**
+     * @brief Same as within_radius(), but the distance is the sum of the differences along each axis
+     */
+    pub fn within_manhattan(&self, center: [C; D], radius: u64) -> Region<'_, T, C, D> {
+        self.within(Ball { center, radius: radius as f64, metric: Metric::Manhattan })
+    }
 
-        /*
-         * Gaurantees:
-         * 1. allocated_node WILL be used, and only once for allocating the particular node
-         * 2. Each index will be used ONLY once
-         *
-         * Notes:
-         * 1. Modify coord of allocated_ids[0] & allocated_ids[1] when using
-         */
+    /**
+     * @brief Same as within_radius(), but the distance is the largest difference along any axis, ie. a cube around `center`
+     */
+    pub fn within_chebyshev(&self, center: [C; D], radius: u64) -> Region<'_, T, C, D> {
+        self.within(Ball { center, radius: radius as f64, metric: Metric::Chebyshev })
+    }
+
+    fn within(&self, ball: Ball<C, D>) -> Region<'_, T, C, D> {
+        // Box around the ball, clamped to the range of C
+        // In i128, since a reach of i64::MAX from 0 must still get down to i64::MIN
+        let reach = ball.radius.floor() as i128;   // saturates for radius too large for i128
+        let clamp = |n: i128| i64::try_from(n).ok().and_then(C::from_i64);
+        let min = ball.center.map(|c| clamp((c.to_i64() as i128).saturating_sub(reach)).unwrap_or(C::MIN));
+        let max = ball.center.map(|c| clamp((c.to_i64() as i128).saturating_add(reach)).unwrap_or(C::MAX));
+
+        // For a negative radius min ends up greater than max, so nothing is visited
+        Region {
+            walk: self.region_walk(min, max, Some(ball)),
+            graphmat: self,
+        }
+    }
 
-        let extra_allocated_ids = [
-            self.default_allocate_one_node(coord),
-            self.default_allocate_one_node(coord),
-        ];
-        let mut i = 0; // points to the 'next' available allocation
+    // An empty walk if `min` is greater than `max` along any axis
+    fn region_walk(&self, min: [C; D], max: [C; D], ball: Option<Ball<C, D>>) -> RegionWalk<C, D> {
+        let leaders = if (0..D).all(|axis| min[axis] <= max[axis]) {
+            self.leaders_in_box(min, max, ball.as_ref())
+        } else {
+            Vec::new()
+        };
 
-        let allocated_node = self.allocate_one_node(data, coord);
+        RegionWalk {
+            min,
+            max,
+            ball,
+            leaders: leaders.into_iter(),
+            stack: Vec::new(),
+        }
+    }
 
-        // ![END] Allocating some nodes in advance
+    /**
+     * @returns Indices of the allocated leaders whose block overlaps the box from `min` to `max`, and `ball` if given
+     */
+    pub(crate) fn leaders_in_box(&self, min: [C; D], max: [C; D], ball: Option<&Ball<C, D>>) -> Vec<IndexInArena> {
+        let (first, last) = (self.leader_coord(min), self.leader_coord(max));
+
+        // Leaders of blocks lying completely outside the ball are skipped, without following any of their links
+        let overlaps_ball = |leader: &[C; D]| {
+            ball.is_none_or(|ball| {
+                ball.overlaps(*leader, self.block_end(*leader))
+            })
+        };
 
-        // SAFETY: 1. self.map contains the leader node, as handled by the previous if, so get.unwrap must not fail
-        //         2. If self.map contains the index as a value at any coord, self.arena must have allocated it
-        let leader = self
-            .arena
-            .get_mut(self.map.get(&leader_coord).unwrap().clone())
-            .unwrap();
+        // Number of leader positions in the box, if that is less than the allocated leaders, look each one up
+        // instead of going through all of self.map
+        // Computed in i128, since for C = i64 the difference between the leaders may not fit an i64
+        let mut positions: u128 = 1;
+        for axis in 0..D {
+            let along_axis = (last[axis].to_i64() as i128 - first[axis].to_i64() as i128) / self.block_size as i128 + 1;
+            positions = positions.saturating_mul(along_axis as u128);
+        }
 
-        let diff = (
-            coord.0 - leader_coord.0,
-            coord.1 - leader_coord.1,
-            coord.2 - leader_coord.2,
-        );
-        match diff {
-            (1, 0, 0) => {
-                // east
-                match leader.east {
-                    None => {
-                        leader.east.replace(allocated_node);
-                    }
-                    Some(idx) => {
-                        // More Correct way is `self.arena.get_mut(idx).unwrap().set(data)`... but `data` was moved earlier for allocating `allocated_node`
-                        leader.east.replace(allocated_node);
-                        self.arena.remove(idx);
-                    }
+        if positions > self.map.len() as u128 {
+            return self
+                .map
+                .iter()
+                .filter(|(leader, _)| (0..D).all(|axis| (first[axis]..=last[axis]).contains(&leader[axis])))
+                .filter(|(leader, _)| overlaps_ball(leader))
+                .map(|(_, idx)| *idx)
+                .collect();
+        }
+
+        let mut leaders = Vec::new();
+        let mut leader = first;
+        'positions: loop {
+            if let Some(idx) = self.map.get(&leader) {
+                if overlaps_ball(&leader) {
+                    leaders.push(*idx);
                 }
             }
-            (0, 1, 0) => {
-                // north
-                match leader.north {
-                    None => {
-                        leader.north.replace(allocated_node);
-                    }
-                    Some(idx) => {
-                        // More Correct way is `self.arena.get_mut(idx).unwrap().set(data)`... but `data` was moved earlier for allocating `allocated_node`
-                        leader.north.replace(allocated_node);
-                        self.arena.remove(idx);
-                    }
+
+            // Next position, like an odometer with each axis going from `first` to `last`
+            for axis in 0..D {
+                if leader[axis] < last[axis] {
+                    // SAFETY: leader < last, and `last` is itself a leader, so this is in range of C
+                    leader[axis] = leader[axis].offset(self.block_size).unwrap();
+                    continue 'positions;
                 }
+                leader[axis] = first[axis];
             }
-            (0, 0, 1) => {
-                // sky
-                match leader.sky {
-                    None => {
-                        leader.sky.replace(allocated_node);
-                    }
-                    Some(idx) => {
-                        // More Correct way is `self.arena.get_mut(idx).unwrap().set(data)`... but `data` was moved earlier for allocating `allocated_node`
-                        leader.sky.replace(allocated_node);
-                        self.arena.remove(idx);
-                    }
-                }
+
+            return leaders;
+        }
+    }
+
+    /**
+     * @brief Creates a matrix with `f` applied to the value of every cell
+     *
+     * The nodes, links and leaders are laid out exactly as in this matrix, so nothing is looked up or hashed again
+     */
+    pub fn map<U, F>(&self, mut f: F) -> GraphMat<U, C, D>
+    where
+        F: FnMut(&T) -> U,
+    {
+        self.map_cells(|_, data| f(data))
+    }
+
+    /**
+     * @brief Replaces the value of every cell with `f` applied to it
+     *
+     * `f` only borrows the value, so if it panics every cell still has its (old or new) value
+     */
+    pub fn map_in_place<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> T,
+    {
+        for (_, node) in self.arena.iter_mut() {
+            if let Some(data) = node.get_mut() {
+                *data = f(data);
             }
-            (0, 1, 1) => {
-                // north-sky
-                let leader_north_idx = match leader.north {
-                    None => {
-                        let index = extra_allocated_ids[i];
-                        leader.north.replace(index);
+        }
+    }
 
-                        // Coordinate of 'north' (ie. y coordinate +1 ) of leader node
-                        self.arena.get_mut(index).unwrap().coord =
-                            (leader_coord.0, leader_coord.1 + 1, leader_coord.2);
-                        i += 1;
+    // Same as map(), but `f` is also given the coordinate of each cell
+    pub(crate) fn map_cells<U, F>(&self, mut f: F) -> GraphMat<U, C, D>
+    where
+        F: FnMut([C; D], &T) -> U,
+    {
+        let slot = |idx: IndexInArena| idx.into_raw_parts().0;
 
-                        debug_assert!(i == 1, "By here i (next free extra allocation) must be 1");
+        // Nodes are inserted in the order of their slots, so new_index[slot] is where each node goes in the new arena
+        let mut arena = Arena::with_capacity(self.arena.len());
+        let mut new_index = vec![None; self.arena.capacity()];
+        for (idx, node) in self.arena.iter() {
+            new_index[slot(idx)] = Some(arena.insert(Node::new(node.get().map(|data| f(node.coord, data)), node.coord)));
+        }
 
-                        index
-                    }
-                    Some(i) => i,
-                };
+        // SAFETY: Every node links only to allocated nodes, which were all given a new index above
+        let translate = |idx: IndexInArena| new_index[slot(idx)].unwrap();
+        for (idx, node) in self.arena.iter() {
+            arena.get_mut(translate(idx)).unwrap().links = node.links.map(|link| link.map(translate));
+        }
 
-                // SAFETY: If a node contains the index as a neighbour, self.arena must have allocated it
-                let leader_north = self.arena.get_mut(leader_north_idx).unwrap();
+        // Cloning keeps the buckets of the HashMap as they are, so the leaders are not hashed again
+        let mut map = self.map.clone();
+        for idx in map.values_mut() {
+            *idx = translate(*idx);
+        }
 
-                match leader_north.sky {
-                    None => {
-                        leader_north.sky.replace(allocated_node);
-                    }
-                    Some(idx) => {
-                        // More Correct way is `self.arena.get_mut(idx).unwrap().set(data)`... but `data` was moved earlier for allocating `allocated_node`
-                        leader_north.sky.replace(allocated_node);
-                        self.arena.remove(idx);
-                    }
-                }
+        GraphMat {
+            arena,
+            map,
+            len: self.len,
+            block_size: self.block_size,
+            bounds: self.bounds.clone(),
+        }
+    }
+
+    pub fn find(&self, value: &T) -> Option<[C; D]>
+    where
+        T: PartialEq,
+    {
+        // Finding in self.map will be more costly given space optimisation is done there
+        // for eg. 100 nodes hai, 10 centers, to har center ke liye recursively search krna hoga
+        //         keeping track of all searched nodes, taaki loop me na phase
+        for (_, node) in self.arena.iter() {
+            if node.get() == Some(value) {
+                return Some(node.coord);
             }
-            (1, 0, 1) => {
-                // east-sky
-                let leader_east_idx = match leader.east {
-                    None => {
-                        let index = extra_allocated_ids[i];
-                        leader.east.replace(index);
+        }
 
-                        // Coordinate of 'east' (ie. x coordinate +1 ) of leader node
-                        self.arena.get_mut(index).unwrap().coord =
-                            (leader_coord.0 + 1, leader_coord.1, leader_coord.2);
-                        i += 1;
+        None
+    }
+
+    pub fn find_if<UnaryPredicate>(&self, pred: UnaryPredicate) -> Option<[C; D]>
+    where
+        UnaryPredicate: Fn(&T) -> bool,
+    {
+        for (_, node) in self.arena.iter() {
+            // SAFETY: First check assures that node.get() contains something, so node.get().unwrap() must not fail
+            if node.get().is_some() && pred(node.get().unwrap()) {
+                return Some(node.coord);
+            }
+        }
 
-                        debug_assert!(i == 1, "By here i (next free extra allocation) must be 1");
+        None
+    }
 
-                        index
-                    }
-                    Some(i) => i,
-                };
+    /**
+     * @brief Takes the value out of the cell at `coord`, leaving it empty
+     *
+     * Placeholder nodes (and the leader) that are no longer needed to reach any data are freed as well
+     */
+    pub fn remove(&mut self, coord: [C; D]) -> Option<T> {
+        let leader_coord = self.leader_coord(coord);
+
+        // Nodes from the leader to `coord`, alongwith the axis of the link that was followed to reach each of them
+        let mut path = vec![(None, *self.map.get(&leader_coord)?)];
+        for (axis, _) in self.links_from_leader(coord) {
+            let (_, curr) = path[path.len() - 1];
+            // SAFETY: If self.map or a node refers to an index, it must be allocated in self.arena
+            let next = self.arena.get(curr).unwrap().links[axis]?;
+            path.push((Some(axis), next));
+        }
 
-                // SAFETY: If a node contains the index as a neighbour, self.arena must have allocated it
-                let leader_east = self.arena.get_mut(leader_east_idx).unwrap();
+        // SAFETY: `path` only contains indices allocated in self.arena
+        let data = self.arena.get_mut(path[path.len() - 1].1).unwrap().take()?;
+        self.cell_vacated(coord);
 
-                match leader_east.sky {
-                    None => {
-                        leader_east.sky.replace(allocated_node);
-                    }
-                    Some(idx) => {
-                        // More Correct way is `self.arena.get_mut(idx).unwrap().set(data)`... but `data` was moved earlier for allocating `allocated_node`
-                        leader_east.sky.replace(allocated_node);
-                        self.arena.remove(idx);
-                    }
+        // Free nodes bottom-up, stopping at the first one that still has data or leads to some other node
+        while let Some((axis, idx)) = path.pop() {
+            // SAFETY: `path` only contains indices allocated in self.arena
+            let node = self.arena.get(idx).unwrap();
+            if node.get().is_some() || node.has_links() {
+                break;
+            }
+
+            self.arena.remove(idx);
+            match (axis, path.last()) {
+                (Some(axis), Some((_, parent))) => {
+                    self.arena.get_mut(*parent).unwrap().links[axis].take();
+                }
+                _ => {
+                    // Only the leader is reached without following a link
+                    self.map.remove(&leader_coord);
                 }
             }
-            (1, 1, 0) => {
-                // north-east
-                let leader_north_idx = match leader.north {
-                    None => {
-                        let index = extra_allocated_ids[i];
-                        leader.north.replace(index);
+        }
 
-                        // Coordinate of 'north' (ie. y coordinate +1 ) of leader node
-                        self.arena.get_mut(index).unwrap().coord =
-                            (leader_coord.0, leader_coord.1 + 1, leader_coord.2);
-                        i += 1;
+        Some(data)
+    }
 
-                        debug_assert!(i == 1, "By here i (next free extra allocation) must be 1");
+    pub fn free_pos(&mut self, coord: [C; D]) {
+        self.remove(coord);
+    }
 
-                        index
-                    }
-                    Some(i) => i,
-                };
+    pub fn free_all<UnaryPredicate>(&mut self, predicate: UnaryPredicate)
+    where
+        UnaryPredicate: Fn(&T) -> bool,
+    {
+        let to_remove: Vec<_> = self
+            .arena
+            .iter()
+            .filter(|(_, node)| node.get().is_some_and(&predicate))
+            .map(|(_, node)| node.coord)
+            .collect();
 
-                // SAFETY: If a node contains the index as a neighbour, self.arena must have allocated it
-                let leader_north = self.arena.get_mut(leader_north_idx).unwrap();
+        for coord in to_remove {
+            self.remove(coord);
+        }
+    }
 
-                match leader_north.east {
-                    None => {
-                        leader_north.east.replace(allocated_node);
-                    }
-                    Some(idx) => {
-                        // More Correct way is `self.arena.get_mut(idx).unwrap().set(data)`... but `data` was moved earlier for allocating `allocated_node`
-                        leader_north.east.replace(allocated_node);
-                        self.arena.remove(idx);
-                    }
-                }
-            }
-            (1, 1, 1) => {
-                // north-east-sky
-                let leader_north_idx = match leader.north {
-                    None => {
-                        let index = extra_allocated_ids[i];
-                        leader.north.replace(index);
+    /**
+     * @returns Number of cells having some data
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
 
-                        // Coordinate of 'north' (ie. y coordinate +1 ) of leader node
-                        self.arena.get_mut(index).unwrap().coord =
-                            (leader_coord.0, leader_coord.1 + 1, leader_coord.2);
-                        i += 1;
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 
-                        debug_assert!(i == 1, "By here i (next free extra allocation) must be 1");
+    /**
+     * @returns Inclusive (min, max) corners of the smallest box containing all cells having some data, None if empty
+     *
+     * This is tracked while setting cells, only after removing a cell lying on the boundary are the cells scanned again
+     */
+    pub fn bounds(&self) -> Option<([C; D], [C; D])> {
+        *self
+            .bounds
+            .get_or_init(|| self.keys().fold(None, |bounds, coord| Some(grow_bounds(bounds, coord))))
+    }
 
-                        index
-                    }
-                    Some(i) => i,
-                };
+    /**
+     * @returns Smallest coordinate along `axis` of the cells having some data, None if empty
+     */
+    pub fn min_along(&self, axis: usize) -> Option<C> {
+        self.bounds().map(|(min, _)| min[axis])
+    }
 
-                // SAFETY: If a node contains the index as a neighbour, self.arena must have allocated it
-                let leader_north = self.arena.get_mut(leader_north_idx).unwrap();
+    /**
+     * @returns Largest coordinate along `axis` of the cells having some data, None if empty
+     */
+    pub fn max_along(&self, axis: usize) -> Option<C> {
+        self.bounds().map(|(_, max)| max[axis])
+    }
 
-                let leader_north_east_idx = match leader_north.east {
-                    None => {
-                        let index = extra_allocated_ids[i];
-                        leader_north.east.replace(index);
+    /**
+     * @returns Number of nodes having some data, same as len()
+     */
+    pub fn node_count(&self) -> usize {
+        self.len
+    }
 
-                        // Coordinate of 'north-east' (ie. x coordinate +1, y coordinate +1 ) of leader node
-                        self.arena.get_mut(index).unwrap().coord =
-                            (leader_coord.0 + 1, leader_coord.1 + 1, leader_coord.2);
-                        i += 1;
+    /**
+     * @returns Number of nodes allocated, ie. len() plus the placeholder nodes only required to reach other nodes
+     */
+    pub fn allocated_nodes(&self) -> usize {
+        self.arena.len()
+    }
 
-                        debug_assert!(i == 2, "By here i (next free extra allocation) must be 2");
+    pub fn reserve(&mut self, capacity: usize) {
+        let cells_per_leader = (self.block_size as usize).saturating_pow(D as u32);
+        self.map.reserve(capacity.saturating_sub(self.map.len()) / cells_per_leader);
+        self.arena.reserve(capacity.saturating_sub(self.arena.len()));
+    }
+}
 
-                        index
-                    }
-                    Some(i) => i,
-                };
+// Only for 3D matrices, since `Direction` is 3D
+impl<T, C: Coord> GraphMat<T, C> {
+    pub fn min_x(&self) -> Option<C> {
+        self.min_along(EAST)
+    }
 
-                // SAFETY: If a node contains the index as a neighbour, self.arena must have allocated it
-                let leader_north_east = self.arena.get_mut(leader_north_east_idx).unwrap();
+    pub fn max_x(&self) -> Option<C> {
+        self.max_along(EAST)
+    }
 
-                match leader_north_east.sky {
-                    None => {
-                        leader_north_east.sky.replace(allocated_node);
-                    }
-                    Some(idx) => {
-                        // More Correct way is `self.arena.get_mut(idx).unwrap().set(data)`... but `data` was moved earlier for allocating `allocated_node`
-                        leader_north_east.sky.replace(allocated_node);
-                        self.arena.remove(idx);
-                    }
-                }
-            }
+    pub fn min_y(&self) -> Option<C> {
+        self.min_along(NORTH)
+    }
 
-            _ => {
-                panic!(
-                    "Invalid direction, coord {:?} is probably not a neighbour of {:?}",
-                    coord, leader_coord
-                );
-            }
-        };
+    pub fn max_y(&self) -> Option<C> {
+        self.max_along(NORTH)
+    }
 
-        // Clean up allocated nodes, that were not used
-        while i != 2 {
-            self.arena.remove(extra_allocated_ids[i]);
-            i += 1;
-        }
+    pub fn min_z(&self) -> Option<C> {
+        self.min_along(SKY)
+    }
+
+    pub fn max_z(&self) -> Option<C> {
+        self.max_along(SKY)
+    }
+
+    /**
+     * @brief Creates a matrix from a dense 3D Vec, the value at dense[i][j][k] goes to origin + (i,j,k)
+     */
+    pub fn from_dense(dense: &[Vec<Vec<T>>], origin: [C; 3]) -> Self
+    where
+        T: Clone,
+    {
+        let cells = dense.iter().enumerate().flat_map(|(i, plane)| {
+            plane.iter().enumerate().flat_map(move |(j, row)| {
+                row.iter().enumerate().map(move |(k, data)| {
+                    let coord = (
+                        origin[0].offset(i as i64),
+                        origin[1].offset(j as i64),
+                        origin[2].offset(k as i64),
+                    );
+                    match coord {
+                        (Some(x), Some(y), Some(z)) => ([x, y, z], data.clone()),
+                        _ => panic!("Dense matrix at {:?} goes out of range of the coordinate type", origin),
+                    }
+                })
+            })
+        });
+
+        cells.collect()
     }
 
     /**
      * @brief Returns an iterator that allows iterating in ONLY ONE DIRECTION
      *
+     * The direction is given as a marker type, for eg. .iter_dir::<markers::Purva>(coord)
      * If the direction is NOT known at compile time, use .iter_all_dir()
      */
-    pub fn iter<'a, const DIR: Direction>(
+    pub fn iter_dir<'a, DIR: DirectionMarker>(
         &'a mut self,
-        starting_coord: (i32, i32, i32),
-    ) -> GraphMatIterator<'a, T, DIR> {
+        starting_coord: [C; 3],
+    ) -> GraphMatIterator<'a, T, DIR, C> {
         GraphMatIterator {
             curr_node_idx: self.get_node_index(starting_coord),
             graphmat: self,
             curr_pos: starting_coord,
+            dir: PhantomData,
         }
     }
 
     /**
      * @brief Returns an iterator that allows iterating in ANY direction
      *
-     * If the direction is known at compile time, can also use .iter(), it
-     * uses compile-time const generics that provides extra efficiency equaivalent to removal of one `match` statement :)
+     * If the direction is known at compile time, can also use .iter_dir(), it
+     * uses a compile-time direction marker that provides extra efficiency equaivalent to removal of one `match` statement :)
      */
     pub fn iter_all_dir<'a>(
         &'a mut self,
-        starting_coord: (i32, i32, i32),
+        starting_coord: [C; 3],
         starting_dir: Direction,
-    ) -> GraphMatFreeIterator<'a, T> {
+    ) -> GraphMatFreeIterator<'a, T, C> {
         GraphMatFreeIterator {
             curr_node_idx: self.get_node_index(starting_coord),
             graphmat: self,
             curr_pos: starting_coord,
             curr_dir: starting_dir,
+            last: None,
         }
     }
 
-    pub fn find(&self, value: &T) -> Option<(i32, i32, i32)>
-    where
-        T: PartialEq,
-    {
-        // Finding in self.map will be more costly given space optimisation is done there
-        // for eg. 100 nodes hai, 10 centers, to har center ke liye recursively search krna hoga
-        //         keeping track of all searched nodes, taaki loop me na phase
-        for (_, node) in self.arena.iter() {
-            if node.get().contains(&value) {
-                return Some(node.coord);
-            }
+    /**
+     * @brief Same as .iter_dir(), but only borrows the matrix immutably, and implements `Iterator`
+     */
+    pub fn iter_dir_ref<DIR: DirectionMarker>(
+        &self,
+        starting_coord: [C; 3],
+    ) -> GraphMatRefIterator<'_, T, DIR, C> {
+        GraphMatRefIterator {
+            curr_node_idx: self.get_node_index(starting_coord),
+            graphmat: self,
+            curr_pos: starting_coord,
+            dir: PhantomData,
         }
+    }
 
-        None
+    /**
+     * @brief Iterates over the neighbours of `coord` having some data, yielding the direction to each alongwith its coordinate
+     *
+     * The leader of `coord` is looked up once, and only the blocks of the neighbours lying outside its block are looked up,
+     * once per block
+     */
+    pub fn neighbours(&self, coord: [C; 3], connectivity: Connectivity) -> Neighbours<'_, T, C> {
+        let leader_coord = self.leader_coord(coord);
+        let leader = self.map.get(&leader_coord).copied();
+
+        Neighbours {
+            graphmat: self,
+            center: coord,
+            center_node_idx: leader.and_then(|leader| match self.walk_from_leader(leader, coord) {
+                Walk::Found(idx) => Some(idx),
+                Walk::Missing { .. } => None,
+            }),
+            connectivity,
+            directions: ALL.into_iter(),
+            leaders: vec![(leader_coord, leader)],
+        }
+    }
+
+    /**
+     * @brief Same as .iter_all_dir(), but only borrows the matrix immutably, and implements `Iterator`
+     */
+    pub fn iter_all_dir_ref(
+        &self,
+        starting_coord: [C; 3],
+        starting_dir: Direction,
+    ) -> GraphMatFreeRefIterator<'_, T, C> {
+        GraphMatFreeRefIterator {
+            curr_node_idx: self.get_node_index(starting_coord),
+            graphmat: self,
+            curr_pos: starting_coord,
+            curr_dir: starting_dir,
+            last: None,
+        }
     }
+}
+
+impl<T, C: Coord, const D: usize> IntoIterator for GraphMat<T, C, D> {
+    type Item = ([C; D], T);
+    type IntoIter = IntoIter<T, C, D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            remaining: self.len,
+            inner: self.arena.into_iter(),
+        }
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> IntoIterator for &'a GraphMat<T, C, D> {
+    type Item = ([C; D], &'a T);
+    type IntoIter = Iter<'a, T, C, D>;
 
-    pub fn find_if<UnaryPredicate>(&self, pred: UnaryPredicate) -> Option<(i32, i32, i32)>
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> IntoIterator for &'a mut GraphMat<T, C, D> {
+    type Item = ([C; D], &'a mut T);
+    type IntoIter = IterMut<'a, T, C, D>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, C: Coord, const D: usize> Extend<([C; D], T)> for GraphMat<T, C, D> {
+    fn extend<I>(&mut self, iter: I)
     where
-        UnaryPredicate: Fn(&T) -> bool,
+        I: IntoIterator<Item = ([C; D], T)>,
     {
-        for (_, node) in self.arena.iter() {
-            // SAFETY: First check assures that node.get() contains something, so node.get().unwrap() must not fail
-            if node.get().is_some() && pred(node.get().unwrap()) {
-                return Some(node.coord);
-            }
+        // Grouping by the leader, so that each leader is looked up in self.map (or allocated) only once
+        let mut blocks: HashMap<_, Vec<_>> = HashMap::new();
+        for (coord, data) in iter {
+            blocks.entry(self.leader_coord(coord)).or_default().push((coord, data));
         }
 
-        None
-    }
+        self.arena.reserve(blocks.values().map(Vec::len).sum());
 
-    pub fn free_pos(&mut self, coord: (i32, i32, i32)) {
-        let index = self.map.get(&coord).clone();
+        for (leader_coord, cells) in blocks {
+            let leader = match self.map.get(&leader_coord) {
+                Some(idx) => *idx,
+                None => {
+                    let index = self.default_allocate_one_node(leader_coord);
+                    self.map.insert(leader_coord, index);
+                    index
+                }
+            };
 
-        if let Some(idx) = index {
-            let idx = idx.clone();
-            self.map.remove(&coord);
-            self.arena.remove(idx);
+            // Cells of a block keep their relative order, so a later value for the same coord still wins
+            for (coord, data) in cells {
+                let walk = self.walk_from_leader(leader, coord);
+                let idx = self.complete_walk(coord, walk);
+                self.set_node(idx, data);
+            }
         }
     }
+}
 
-    pub fn free_all<UnaryPredicate>(&mut self, predicate: UnaryPredicate)
+impl<T, C: Coord, const D: usize> FromIterator<([C; D], T)> for GraphMat<T, C, D> {
+    fn from_iter<I>(iter: I) -> Self
     where
-        UnaryPredicate: Fn(&T) -> bool,
+        I: IntoIterator<Item = ([C; D], T)>,
     {
-        let mut to_remove = Vec::new();
-        for (coord, idx) in self.map.iter() {
-            // SAFETY: Since, self.map contains the coordinate and corresponding `idx`,
-            // there must be some existing node at `idx`, so self.arena.get(idx) must return Some(node)
-            let node = self.arena.get(*idx).unwrap();
-            // SAFETY: First check assures that node.get() contains something, so node.get().unwrap() must not fail
-            if node.get().is_some() && predicate(node.get().unwrap()) {
-                to_remove.push(coord.clone());
-            }
+        let mut matrix = GraphMat::new();
+        matrix.extend(iter);
+
+        matrix
+    }
+}
+
+impl<T, C: Coord, const D: usize> Default for GraphMat<T, C, D> {
+    fn default() -> Self {
+        GraphMat::new()
+    }
+}
+
+impl<T, C: Coord, const D: usize> Index<[C; D]> for GraphMat<T, C, D> {
+    type Output = T;
+
+    fn index(&self, coord: [C; D]) -> &T {
+        match self.get(coord) {
+            Some(data) => data,
+            None => panic!("No data at coord {:?}", coord),
         }
+    }
+}
 
-        for coord in to_remove {
-            // SAFETY: `to_remove` was filled with self.map keys, so map.get must not fail
-            let idx = self.map.get(&coord).unwrap().clone();
-            self.arena.remove(idx);
-            self.map.remove(&coord);
+impl<T, C: Coord, const D: usize> IndexMut<[C; D]> for GraphMat<T, C, D> {
+    fn index_mut(&mut self, coord: [C; D]) -> &mut T {
+        match self.get_mut(coord) {
+            Some(data) => data,
+            None => panic!("No data at coord {:?}", coord),
         }
     }
+}
+
+// Prints only the cells having some data, as a map of coord => value
+impl<T: fmt::Debug, C: Coord, const D: usize> fmt::Debug for GraphMat<T, C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
 
-    pub fn reserve(&mut self, capacity: usize) {
-        self.map.reserve((capacity - self.map.len())/8);
-        self.arena.reserve(capacity - self.arena.len());
+// Two matrices are equal if the same cells have equal values, irrespective of how the nodes are laid out in the arena
+impl<T: PartialEq, C: Coord, const D: usize> PartialEq for GraphMat<T, C, D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|(coord, data)| other.get(coord) == Some(data))
     }
 }
+
+impl<T: Eq, C: Coord, const D: usize> Eq for GraphMat<T, C, D> {}
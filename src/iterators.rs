@@ -1,113 +1,156 @@
-use generational_arena::Index as IndexInArena;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use generational_arena::{Arena, Index as IndexInArena};
+
+use crate::coord::Coord;
+use crate::direction::{Connectivity, Direction, DirectionMarker};
+use crate::graphmat::{GraphMat, Walk};
+use crate::metric::Ball;
+use crate::node::{Node, EAST, NORTH, SKY};
+
+/**
+ * @returns coord + increment, None if that overflows `C`
+ */
+fn inc_coord<C: Coord>(coord: [C; 3], increment: [i64; 3]) -> Option<[C; 3]> {
+    Some([
+        coord[0].offset(increment[0])?,
+        coord[1].offset(increment[1])?,
+        coord[2].offset(increment[2])?,
+    ])
+}
+
+// Increment to the coordinate on moving in `dir`, and the axis of the link that directly leads there (if any)
+fn step_of(dir: &Direction) -> ([i64; 3], Option<usize>) {
+    let increment = dir.offset().map(i64::from);
+
+    // Links only lead +1 along a single axis
+    let axis = match increment {
+        [1, 0, 0] => Some(EAST),
+        [0, 1, 0] => Some(NORTH),
+        [0, 0, 1] => Some(SKY),
+        _ => None,
+    };
 
-use crate::direction::Direction;
-use crate::graphmat::GraphMat;
+    (increment, axis)
+}
+
+/**
+ * @brief Position and index of the node one step in `dir` from the node at `pos`
+ *
+ * @returns None if the position is out of range of `C`, for eg. going west from x = 0 with u32
+ */
+fn step<T, C: Coord>(
+    graphmat: &GraphMat<T, C>,
+    pos: [C; 3],
+    node_idx: IndexInArena,
+    dir: &Direction,
+) -> Option<([C; 3], Option<IndexInArena>)> {
+    let (increment, axis) = step_of(dir);
+    let next_pos = inc_coord(pos, increment)?;
+
+    // SAFETY: The iterator only holds indices returned by the matrix, which it borrows, so they can't be freed meanwhile
+    let node = graphmat.arena.get(node_idx).unwrap();
+
+    // TODO: Only north/east/sky neighbours are reached through the links, others still go through the leader
+    match axis.and_then(|axis| node.links[axis]) {
+        Some(idx) => Some((next_pos, Some(idx))),
+        None => Some((next_pos, graphmat.get_node_index(next_pos))),
+    }
+}
+
+/**
+ * @brief Moves `curr_pos` and `curr_node_idx` one step in `dir`, common to all directional iterators
+ *
+ * @returns Position and index of the node before the step, None once a cell without data is reached
+ */
+fn advance<T, C: Coord>(
+    graphmat: &GraphMat<T, C>,
+    curr_pos: &mut [C; 3],
+    curr_node_idx: &mut Option<IndexInArena>,
+    dir: &Direction,
+) -> Option<([C; 3], IndexInArena)> {
+    let prev_pos = *curr_pos;
+    let prev_node_idx = (*curr_node_idx)?;
+
+    // SAFETY: The iterator only holds indices returned by the matrix, which it borrows, so they can't be freed meanwhile
+    if graphmat.arena.get(prev_node_idx).unwrap().get().is_none() {
+        *curr_node_idx = None;
+        return None;
+    }
+
+    match step(graphmat, prev_pos, prev_node_idx, dir) {
+        Some((pos, idx)) => (*curr_pos, *curr_node_idx) = (pos, idx),
+        None => *curr_node_idx = None,
+    }
 
-fn inc_coord(coord: (i32, i32, i32), increment: (i32, i32, i32)) -> (i32, i32, i32) {
-    (
-        coord.0 + increment.0,
-        coord.1 + increment.1,
-        coord.2 + increment.2,
-    )
+    Some((prev_pos, prev_node_idx))
 }
 
-pub struct GraphMatFreeIterator<'a, T> {
-    pub(crate) graphmat: &'a mut GraphMat<T>,
-    pub curr_pos: (i32, i32, i32),
+pub struct GraphMatFreeIterator<'a, T, C = i32> {
+    pub(crate) graphmat: &'a mut GraphMat<T, C>,
+    pub curr_pos: [C; 3],
     pub curr_node_idx: Option<IndexInArena>,
     pub curr_dir: Direction,
+    // Position and index of the node last returned by next()
+    pub(crate) last: Option<([C; 3], IndexInArena)>,
 }
 
-impl<'a, T> GraphMatFreeIterator<'a, T> {
+impl<'a, T, C: Coord> GraphMatFreeIterator<'a, T, C> {
     /**
+     * @brief Changes the direction of iteration, the next node will be the neighbour (in `dir`) of the node last returned
+     *
      * @returns Previous 'direction of iteration'
      */
     pub fn set_direction(&mut self, dir: Direction) -> Direction {
-        let old_dir = self.curr_dir.clone();
-
-        self.curr_dir = dir;
-
-        old_dir
-    }
-}
-
-impl<'a, T> GraphMatFreeIterator<'a, T> {
-    pub fn next<'b>(&'b mut self) -> Option<((i32,i32,i32), &'b T)> {
-        let prev_pos = self.curr_pos.clone();
-        let prev_node_idx = match self.curr_node_idx {
-            None => { return None; },
-            Some(idx) => idx
-        };
-
-        let prev_node = self.graphmat.arena.get(prev_node_idx).unwrap();
-
-        (self.curr_pos, self.curr_node_idx) = match self.curr_dir {
-            Direction::uttar => {
-                (inc_coord(self.curr_pos, (0, 1, 0)), prev_node.north)
-            },
-            Direction::purva => {
-               (inc_coord(self.curr_pos, (1, 0, 0)), prev_node.east)
-            },
-            Direction::paschim => {
-                let curr_pos = inc_coord(self.curr_pos, (-1, 0, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::dakshin => {
-                let curr_pos = inc_coord(self.curr_pos, (0, -1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-
-            Direction::vayavya => {
-                let curr_pos = inc_coord(self.curr_pos, (-1, 1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::ishanya => {
-                let curr_pos = inc_coord(self.curr_pos, (1, 1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::nairutya => {
-                let curr_pos = inc_coord(self.curr_pos, (-1, -1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::agneya => {
-                let curr_pos = inc_coord(self.curr_pos, (1, -1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-
-            Direction::urdhwa => {
-                (inc_coord(self.curr_pos, (1, 0, 0)), prev_node.sky)
-            },
-            Direction::adharastha => {
-                let curr_pos = inc_coord(self.curr_pos, (0, 0, -1));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
+        if let Some((pos, idx)) = self.last {
+            match step(self.graphmat, pos, idx, &dir) {
+                Some((pos, idx)) => (self.curr_pos, self.curr_node_idx) = (pos, idx),
+                None => self.curr_node_idx = None,
             }
-        };
+        }
+
+        std::mem::replace(&mut self.curr_dir, dir)
+    }
+}
+
+impl<'a, T, C: Coord> GraphMatFreeIterator<'a, T, C> {
+    // Not `Iterator::next`, see the comment above `impl GraphMatIterator`
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<([C; 3], &T)> {
+        let (prev_pos, prev_node_idx) = advance(self.graphmat, &mut self.curr_pos, &mut self.curr_node_idx, &self.curr_dir)?;
+        self.last = Some((prev_pos, prev_node_idx));
+
+        // SAFETY: advance() only returns nodes having some data
+        Some((prev_pos, self.graphmat.arena.get(prev_node_idx).unwrap().get().unwrap()))
+    }
+
+    /**
+     * @brief Same as next(), but the value can be modified, for eg. to update every cell on a line
+     */
+    pub fn next_mut(&mut self) -> Option<([C; 3], &mut T)> {
+        let (prev_pos, prev_node_idx) = advance(self.graphmat, &mut self.curr_pos, &mut self.curr_node_idx, &self.curr_dir)?;
+        self.last = Some((prev_pos, prev_node_idx));
+
+        // SAFETY: advance() only returns nodes having some data
+        Some((prev_pos, self.graphmat.arena.get_mut(prev_node_idx).unwrap().get_mut().unwrap()))
+    }
 
-        match prev_node.get() {
-            None => None,
-            Some(prev_node) => Some((prev_pos, prev_node))
+    pub fn for_each_mut<F>(mut self, mut f: F)
+    where
+        F: FnMut([C; 3], &mut T),
+    {
+        while let Some((coord, data)) = self.next_mut() {
+            f(coord, data);
         }
     }
 }
 
-pub struct GraphMatIterator<'a, T, const DIR: Direction> {
-    pub(crate) graphmat: &'a mut GraphMat<T>,
+pub struct GraphMatIterator<'a, T, DIR: DirectionMarker, C = i32> {
+    pub(crate) graphmat: &'a mut GraphMat<T, C>,
     pub curr_node_idx: Option<IndexInArena>,
-    pub curr_pos: (i32, i32, i32),
+    pub curr_pos: [C; 3],
+    pub(crate) dir: PhantomData<DIR>,
 }
 
 // Implementing `Iterator`, causes lifetime mismatch, it expects the function signatures to match,
@@ -116,76 +159,401 @@ pub struct GraphMatIterator<'a, T, const DIR: Direction> {
 // which it says "this lifetime may not always outlive that" some message like it
 // 
 // Can just try to see that error
-impl<'a, T, const DIR: Direction> GraphMatIterator<'a, T, DIR> {
-    pub fn next<'b>(&'b mut self) -> Option<((i32,i32,i32), &'b T)> {
-        // TODO: There is good scope of improvement here, instead of using graphmat.get() everytime, try using the node to get neighbours
-        let prev_pos = self.curr_pos.clone();
-        let prev_node_idx = match self.curr_node_idx {
-            None => { return None; },
-            Some(idx) => idx
-        };
-
-        let prev_node = self.graphmat.arena.get(prev_node_idx).unwrap();
-
-        (self.curr_pos, self.curr_node_idx) = match DIR {
-            Direction::uttar => {
-                (inc_coord(self.curr_pos, (0, 1, 0)), prev_node.north)
-            },
-            Direction::purva => {
-               (inc_coord(self.curr_pos, (1, 0, 0)), prev_node.east)
-            },
-            Direction::paschim => {
-                let curr_pos = inc_coord(self.curr_pos, (-1, 0, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::dakshin => {
-                let curr_pos = inc_coord(self.curr_pos, (0, -1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-
-            Direction::vayavya => {
-                let curr_pos = inc_coord(self.curr_pos, (-1, 1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::ishanya => {
-                let curr_pos = inc_coord(self.curr_pos, (1, 1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::nairutya => {
-                let curr_pos = inc_coord(self.curr_pos, (-1, -1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-            Direction::agneya => {
-                let curr_pos = inc_coord(self.curr_pos, (1, -1, 0));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
-            },
-
-            Direction::urdhwa => {
-                (inc_coord(self.curr_pos, (1, 0, 0)), prev_node.sky)
-            },
-            Direction::adharastha => {
-                let curr_pos = inc_coord(self.curr_pos, (0, 0, -1));
-                let node_idx = self.graphmat.get_node_index(curr_pos);
-
-                (curr_pos, node_idx)
+//
+// If only reading, use GraphMatRefIterator/GraphMatFreeRefIterator, they borrow the matrix immutably and do implement `Iterator`
+impl<'a, T, DIR: DirectionMarker, C: Coord> GraphMatIterator<'a, T, DIR, C> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<([C; 3], &T)> {
+        let (prev_pos, prev_node_idx) = advance(self.graphmat, &mut self.curr_pos, &mut self.curr_node_idx, &DIR::DIRECTION)?;
+
+        // SAFETY: advance() only returns nodes having some data
+        Some((prev_pos, self.graphmat.arena.get(prev_node_idx).unwrap().get().unwrap()))
+    }
+
+    /**
+     * @brief Same as next(), but the value can be modified, for eg. to update every cell on a line
+     */
+    pub fn next_mut(&mut self) -> Option<([C; 3], &mut T)> {
+        let (prev_pos, prev_node_idx) = advance(self.graphmat, &mut self.curr_pos, &mut self.curr_node_idx, &DIR::DIRECTION)?;
+
+        // SAFETY: advance() only returns nodes having some data
+        Some((prev_pos, self.graphmat.arena.get_mut(prev_node_idx).unwrap().get_mut().unwrap()))
+    }
+
+    pub fn for_each_mut<F>(mut self, mut f: F)
+    where
+        F: FnMut([C; 3], &mut T),
+    {
+        while let Some((coord, data)) = self.next_mut() {
+            f(coord, data);
+        }
+    }
+}
+
+// Read-only versions of the directional iterators, many of these can run over the same matrix at once
+
+pub struct GraphMatFreeRefIterator<'a, T, C = i32> {
+    pub(crate) graphmat: &'a GraphMat<T, C>,
+    pub curr_pos: [C; 3],
+    pub curr_node_idx: Option<IndexInArena>,
+    pub curr_dir: Direction,
+    // Position and index of the node last returned by next()
+    pub(crate) last: Option<([C; 3], IndexInArena)>,
+}
+
+impl<'a, T, C: Coord> GraphMatFreeRefIterator<'a, T, C> {
+    /**
+     * @brief Changes the direction of iteration, the next node will be the neighbour (in `dir`) of the node last returned
+     *
+     * @returns Previous 'direction of iteration'
+     */
+    pub fn set_direction(&mut self, dir: Direction) -> Direction {
+        if let Some((pos, idx)) = self.last {
+            match step(self.graphmat, pos, idx, &dir) {
+                Some((pos, idx)) => (self.curr_pos, self.curr_node_idx) = (pos, idx),
+                None => self.curr_node_idx = None,
+            }
+        }
+
+        std::mem::replace(&mut self.curr_dir, dir)
+    }
+}
+
+impl<'a, T, C: Coord> Iterator for GraphMatFreeRefIterator<'a, T, C> {
+    type Item = ([C; 3], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (prev_pos, prev_node_idx) = advance(self.graphmat, &mut self.curr_pos, &mut self.curr_node_idx, &self.curr_dir)?;
+        self.last = Some((prev_pos, prev_node_idx));
+
+        // SAFETY: advance() only returns nodes having some data
+        Some((prev_pos, self.graphmat.arena.get(prev_node_idx).unwrap().get().unwrap()))
+    }
+}
+
+impl<'a, T, C: Coord> FusedIterator for GraphMatFreeRefIterator<'a, T, C> {}
+
+pub struct GraphMatRefIterator<'a, T, DIR: DirectionMarker, C = i32> {
+    pub(crate) graphmat: &'a GraphMat<T, C>,
+    pub curr_node_idx: Option<IndexInArena>,
+    pub curr_pos: [C; 3],
+    pub(crate) dir: PhantomData<DIR>,
+}
+
+impl<'a, T, DIR: DirectionMarker, C: Coord> Iterator for GraphMatRefIterator<'a, T, DIR, C> {
+    type Item = ([C; 3], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (prev_pos, prev_node_idx) = advance(self.graphmat, &mut self.curr_pos, &mut self.curr_node_idx, &DIR::DIRECTION)?;
+
+        // SAFETY: advance() only returns nodes having some data
+        Some((prev_pos, self.graphmat.arena.get(prev_node_idx).unwrap().get().unwrap()))
+    }
+}
+
+impl<'a, T, DIR: DirectionMarker, C: Coord> FusedIterator for GraphMatRefIterator<'a, T, DIR, C> {}
+
+// Iterators over the whole matrix, these go through the arena directly, skipping placeholder nodes
+//
+// `remaining` is the number of cells having some data that are yet to be yielded, so these can be ExactSizeIterator
+
+pub struct Iter<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) inner: generational_arena::Iter<'a, Node<T, C, D>>,
+    pub(crate) remaining: usize,
+}
+
+impl<'a, T, C: Coord, const D: usize> Iterator for Iter<'a, T, C, D> {
+    type Item = ([C; D], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (_, node) in self.inner.by_ref() {
+            if let Some(data) = node.get() {
+                self.remaining -= 1;
+                return Some((node.coord, data));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> ExactSizeIterator for Iter<'a, T, C, D> {}
+impl<'a, T, C: Coord, const D: usize> FusedIterator for Iter<'a, T, C, D> {}
+
+pub struct IterMut<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) inner: generational_arena::IterMut<'a, Node<T, C, D>>,
+    pub(crate) remaining: usize,
+}
+
+impl<'a, T, C: Coord, const D: usize> Iterator for IterMut<'a, T, C, D> {
+    type Item = ([C; D], &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (_, node) in self.inner.by_ref() {
+            let coord = node.coord;
+            if let Some(data) = node.get_mut() {
+                self.remaining -= 1;
+                return Some((coord, data));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> ExactSizeIterator for IterMut<'a, T, C, D> {}
+impl<'a, T, C: Coord, const D: usize> FusedIterator for IterMut<'a, T, C, D> {}
+
+pub struct IntoIter<T, C = i32, const D: usize = 3> {
+    pub(crate) inner: generational_arena::IntoIter<Node<T, C, D>>,
+    pub(crate) remaining: usize,
+}
+
+impl<T, C: Coord, const D: usize> Iterator for IntoIter<T, C, D> {
+    type Item = ([C; D], T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for mut node in self.inner.by_ref() {
+            if let Some(data) = node.take() {
+                self.remaining -= 1;
+                return Some((node.coord, data));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, C: Coord, const D: usize> ExactSizeIterator for IntoIter<T, C, D> {}
+impl<T, C: Coord, const D: usize> FusedIterator for IntoIter<T, C, D> {}
+
+pub struct Keys<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) inner: Iter<'a, T, C, D>,
+}
+
+impl<'a, T, C: Coord, const D: usize> Iterator for Keys<'a, T, C, D> {
+    type Item = [C; D];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(coord, _)| coord)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> ExactSizeIterator for Keys<'a, T, C, D> {}
+impl<'a, T, C: Coord, const D: usize> FusedIterator for Keys<'a, T, C, D> {}
+
+pub struct Values<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) inner: Iter<'a, T, C, D>,
+}
+
+impl<'a, T, C: Coord, const D: usize> Iterator for Values<'a, T, C, D> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, data)| data)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> ExactSizeIterator for Values<'a, T, C, D> {}
+impl<'a, T, C: Coord, const D: usize> FusedIterator for Values<'a, T, C, D> {}
+
+pub struct ValuesMut<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) inner: IterMut<'a, T, C, D>,
+}
+
+impl<'a, T, C: Coord, const D: usize> Iterator for ValuesMut<'a, T, C, D> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, data)| data)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> ExactSizeIterator for ValuesMut<'a, T, C, D> {}
+impl<'a, T, C: Coord, const D: usize> FusedIterator for ValuesMut<'a, T, C, D> {}
+
+// Iterators over an axis-aligned box, these only visit the leaders overlapping the box and the nodes linked from them
+
+// Depth-first walk over the nodes reached from the leaders, common to Region and RegionMut
+pub(crate) struct RegionWalk<C, const D: usize> {
+    pub(crate) min: [C; D],
+    pub(crate) max: [C; D],
+    // If set, only the cells inside the box that are also inside this ball are yielded
+    pub(crate) ball: Option<Ball<C, D>>,
+    // Leaders yet to be walked
+    pub(crate) leaders: std::vec::IntoIter<IndexInArena>,
+    // Nodes reached but not yet visited
+    pub(crate) stack: Vec<IndexInArena>,
+}
+
+impl<C: Coord, const D: usize> RegionWalk<C, D> {
+    /**
+     * @returns Index of the next node inside the box having some data
+     */
+    pub(crate) fn next_index<T>(&mut self, arena: &Arena<Node<T, C, D>>) -> Option<IndexInArena> {
+        loop {
+            let idx = match self.stack.pop() {
+                Some(idx) => idx,
+                None => self.leaders.next()?,
+            };
+
+            // SAFETY: The iterator borrows the matrix, so indices from its map and links can't be freed meanwhile
+            let node = arena.get(idx).unwrap();
+            for (axis, link) in node.links.iter().enumerate() {
+                // Links only go +1 along an axis, so once past `max` nothing further along that link can be inside
+                if let Some(link) = link {
+                    if node.coord[axis] < self.max[axis] {
+                        self.stack.push(*link);
+                    }
+                }
+            }
+
+            let inside = (0..D).all(|axis| (self.min[axis]..=self.max[axis]).contains(&node.coord[axis]));
+            let inside = inside && self.ball.as_ref().is_none_or(|ball| ball.contains(node.coord));
+            if inside && node.get().is_some() {
+                return Some(idx);
+            }
+        }
+    }
+}
+
+pub struct Region<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) graphmat: &'a GraphMat<T, C, D>,
+    pub(crate) walk: RegionWalk<C, D>,
+}
+
+impl<'a, T, C: Coord, const D: usize> Iterator for Region<'a, T, C, D> {
+    type Item = ([C; D], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.walk.next_index(&self.graphmat.arena)?;
+
+        // SAFETY: next_index() only returns nodes having some data
+        let node = self.graphmat.arena.get(idx).unwrap();
+        Some((node.coord, node.get().unwrap()))
+    }
+}
+
+impl<'a, T, C: Coord, const D: usize> FusedIterator for Region<'a, T, C, D> {}
+
+pub struct RegionMut<'a, T, C = i32, const D: usize = 3> {
+    pub(crate) graphmat: &'a mut GraphMat<T, C, D>,
+    pub(crate) walk: RegionWalk<C, D>,
+}
+
+// Not an `Iterator` for the same reason as GraphMatIterator, see the comment above its impl
+impl<'a, T, C: Coord, const D: usize> RegionMut<'a, T, C, D> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<([C; D], &T)> {
+        let idx = self.walk.next_index(&self.graphmat.arena)?;
+
+        // SAFETY: next_index() only returns nodes having some data
+        let node = self.graphmat.arena.get(idx).unwrap();
+        Some((node.coord, node.get().unwrap()))
+    }
+
+    pub fn next_mut(&mut self) -> Option<([C; D], &mut T)> {
+        let idx = self.walk.next_index(&self.graphmat.arena)?;
+
+        // SAFETY: next_index() only returns nodes having some data
+        let node = self.graphmat.arena.get_mut(idx).unwrap();
+        Some((node.coord, node.get_mut().unwrap()))
+    }
+
+    pub fn for_each_mut<F>(mut self, mut f: F)
+    where
+        F: FnMut([C; D], &mut T),
+    {
+        while let Some((coord, data)) = self.next_mut() {
+            f(coord, data);
+        }
+    }
+}
+
+pub struct Neighbours<'a, T, C = i32> {
+    pub(crate) graphmat: &'a GraphMat<T, C>,
+    pub(crate) center: [C; 3],
+    pub(crate) center_node_idx: Option<IndexInArena>,
+    pub(crate) connectivity: Connectivity,
+    pub(crate) directions: std::array::IntoIter<Direction, 26>,
+    // Leaders looked up so far (None if not allocated), so that each block is looked up in the map only once
+    pub(crate) leaders: Vec<([C; 3], Option<IndexInArena>)>,
+}
+
+impl<'a, T, C: Coord> Neighbours<'a, T, C> {
+    fn leader_index(&mut self, leader_coord: [C; 3]) -> Option<IndexInArena> {
+        if let Some((_, leader)) = self.leaders.iter().find(|(coord, _)| *coord == leader_coord) {
+            return *leader;
+        }
+
+        let leader = self.graphmat.map.get(&leader_coord).copied();
+        self.leaders.push((leader_coord, leader));
+        leader
+    }
+
+    fn node_index(&mut self, dir: &Direction) -> Option<([C; 3], IndexInArena)> {
+        let (increment, axis) = step_of(dir);
+        let pos = inc_coord(self.center, increment)?;
+
+        // East, north and sky neighbours may be directly linked from the node at the center
+        if let (Some(center), Some(axis)) = (self.center_node_idx, axis) {
+            // SAFETY: The iterator borrows the matrix, so indices from its map and links can't be freed meanwhile
+            if let Some(idx) = self.graphmat.arena.get(center).unwrap().links[axis] {
+                return Some((pos, idx));
             }
-        };
+        }
 
-        match prev_node.get() {
-            None => None,
-            Some(prev_node) => Some((prev_pos, prev_node))
+        let leader = self.leader_index(self.graphmat.leader_coord(pos))?;
+        match self.graphmat.walk_from_leader(leader, pos) {
+            Walk::Found(idx) => Some((pos, idx)),
+            Walk::Missing { .. } => None,
         }
     }
 }
+
+impl<'a, T, C: Coord> Iterator for Neighbours<'a, T, C> {
+    type Item = (Direction, [C; 3], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(dir) = self.directions.next() {
+            if !self.connectivity.includes(&dir) {
+                continue;
+            }
+
+            if let Some((pos, idx)) = self.node_index(&dir) {
+                // SAFETY: node_index() only returns indices allocated in the arena
+                if let Some(data) = self.graphmat.arena.get(idx).unwrap().get() {
+                    return Some((dir, pos, data));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, T, C: Coord> FusedIterator for Neighbours<'a, T, C> {}
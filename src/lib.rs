@@ -1,43 +1,58 @@
-#![feature(adt_const_params)] // to use compile time Direction constants in GraphMat::iter()
-#![feature(option_result_contains)] // to use `option.contains(value)` instead of `match option { Some(val) => val == value, None => false }`
+#![cfg_attr(feature = "nightly", feature(adt_const_params))] // to use Direction constants as markers::ConstDir<{ Direction::purva }>
 
+mod components;
+mod coord;
 mod direction;
+mod entry;
 mod graphmat;
 mod iterators;
+mod metric;
+mod nearest;
 mod node;
+pub mod pathfinding;
+mod raycast;
+mod reduce;
+mod slice;
+mod stencil;
+mod zip;
 
-pub use iterators::{GraphMatIterator, GraphMatFreeIterator};
-pub use direction::Direction;
-pub use graphmat::GraphMat;
+pub use iterators::{GraphMatIterator, GraphMatFreeIterator, GraphMatRefIterator, GraphMatFreeRefIterator, IntoIter, Iter, IterMut, Keys, Neighbours, Region, RegionMut, Values, ValuesMut};
+pub use coord::Coord;
+pub use direction::{markers, Axis, Connectivity, Direction, DirectionMarker, Side};
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use graphmat::{GraphMat, GraphMat2D, GraphMat4D};
+pub use raycast::{Hit, Line, Raycast};
+pub use slice::{Slice2D, Slice2DMut, SliceIter};
+pub use stencil::{Boundary, Kernel3D, NeighbourhoodView};
 
 #[test]
 fn main_test() {
     let mut matrix = GraphMat::new();
 
-    matrix.get((1, 2, 3));
-    matrix.set((2, 3, 4), 150);
-    matrix.get_mut((2, 3, 4));
+    matrix.get([1, 2, 3]);
+    matrix.set([2, 3, 4], 150);
+    matrix.get_mut([2, 3, 4]);
 
-    println!("{:?}, {:?}", matrix.get((1, 2, 3)), matrix.get((2, 3, 4)));
+    println!("{:?}, {:?}", matrix.get([1, 2, 3]), matrix.get([2, 3, 4]));
 
-    matrix.set((4, 3, 6), 40);
-    matrix.set((4, 2, 6), 39);
-    matrix.set((4, 1, 6), 38);
-    matrix.set((4, 0, 6), 37);
+    matrix.set([4, 3, 6], 40);
+    matrix.set([4, 2, 6], 39);
+    matrix.set([4, 1, 6], 38);
+    matrix.set([4, 0, 6], 37);
 
-    matrix.set((4, 3, 5), 13);
-    matrix.set((4, 2, 5), 21);
-    matrix.set((4, 1, 5), 22);
-    matrix.set((4, 0, 5), 23);
+    matrix.set([4, 3, 5], 13);
+    matrix.set([4, 2, 5], 21);
+    matrix.set([4, 1, 5], 22);
+    matrix.set([4, 0, 5], 23);
 
-    let mut it = matrix.iter::<{ Direction::dakshin }>((4, 3, 6));
+    let mut it = matrix.iter_dir::<markers::Dakshin>([4, 3, 6]);
 
     // Will run till it.next() is not None
     while let Some((coord, node)) = it.next() {
         println!("Iterating in loop1: {:?} => {}", coord, node);
     }
 
-    let mut it2 = matrix.iter_all_dir((4, 3, 6), Direction::adharastha);
+    let mut it2 = matrix.iter_all_dir([4, 3, 6], Direction::adharastha);
 
     // Will run till it.next() is not None
     while let Some((coord, node)) = it2.next() {
@@ -50,7 +65,7 @@ fn main_test() {
     }
 
     // Conditionally free some nodes
-    matrix.free_pos((2, 3, 4));
+    matrix.free_pos([2, 3, 4]);
     matrix.free_all(|n| n == &0);
 }
 
@@ -62,7 +77,7 @@ fn matrix_set_n() {
     for i in 0..99 {
         for j in 0..99 {
             for k in 0..99 {
-                matrix.set((i, j, k), i + j + k);
+                matrix.set([i, j, k], i + j + k);
             }
         }
     }
@@ -76,7 +91,7 @@ fn matrix_set_n_get() {
     for i in 0..99 {
         for j in 0..99 {
             for k in 0..99 {
-                matrix.set((i, j, k), i + j + k);
+                matrix.set([i, j, k], i + j + k);
             }
         }
     }
@@ -84,7 +99,7 @@ fn matrix_set_n_get() {
     for i in 0..99 {
         for j in 0..99 {
             for k in 0..99 {
-                let node = matrix.get((i, j, k));
+                let node = matrix.get([i, j, k]);
                 assert!(node.is_some());
                 assert_eq!(node.unwrap().clone(), i + j + k);
             }
@@ -99,6 +114,7 @@ fn matrix_set_n_get() {
 }
 
 #[test]
+#[allow(unused_variables, unused_assignments)]
 fn matrix_iterator() {
     let mut matrix = GraphMat::new();
     matrix.reserve(1000000);
@@ -106,7 +122,7 @@ fn matrix_iterator() {
     for i in 0..99 {
         for j in 0..99 {
             for k in 0..99 {
-                matrix.set((i, j, k), i + j + k);
+                matrix.set([i, j, k], i + j + k);
             }
         }
     }
@@ -114,14 +130,14 @@ fn matrix_iterator() {
     // Iterator 'significantly' faster (currently in some directions, it is just an O(1) access)
     for j in 0..99 {
         for k in 0..99 {
-            let mut it = matrix.iter::<{ Direction::purva }>((0, j, k));
+            let mut it = matrix.iter_dir::<markers::Purva>([0, j, k]);
 
             let mut i = 0;
             while let Some((coord, val)) = it.next() {
                 i += 1;
-                assert_eq!(*val, coord.0 + coord.1 + coord.2);
+                assert_eq!(*val, coord[0] + coord[1] + coord[2]);
             }
-    
+
             // println!(
             //     "times: {}, len: {}, capacity: {}",
             //     i,
@@ -133,6 +149,7 @@ fn matrix_iterator() {
 }
 
 #[test]
+#[allow(clippy::needless_range_loop)]
 fn simple_matrix_init() {
     // For benchmarking
     let mut matrix = Vec::new();
@@ -156,3 +173,1057 @@ fn simple_matrix_init() {
         }
     }
 }
+
+#[test]
+fn matrix_entry() {
+    let mut matrix = GraphMat::new();
+
+    // Leader and non-leader coordinates
+    for coord in [[0, 0, 0], [1, 0, 1], [1, 1, 1], [-3, 5, -7]] {
+        *matrix.entry(coord).or_insert(0) += 1;
+        *matrix.entry(coord).or_insert(0) += 1;
+        assert_eq!(matrix.get(coord), Some(&2));
+    }
+
+    matrix.entry([1, 0, 0]).and_modify(|n| *n += 10).or_insert_with(|| 5);
+    assert_eq!(matrix.get([1, 0, 0]), Some(&5));
+    matrix.entry([1, 0, 0]).and_modify(|n| *n += 10).or_insert_with(|| 5);
+    assert_eq!(matrix.get([1, 0, 0]), Some(&15));
+
+    // [1, 0, 0] is a placeholder on the way to [1, 0, 1], setting it must keep [1, 0, 1] reachable
+    assert_eq!(matrix.get([1, 0, 1]), Some(&2));
+
+    assert_eq!(*matrix.entry([4, 4, 5]).or_default(), 0);
+
+    match matrix.entry([1, 1, 1]) {
+        Entry::Occupied(mut entry) => assert_eq!(entry.insert(7), 2),
+        Entry::Vacant(_) => panic!("[1, 1, 1] was set earlier"),
+    }
+    assert_eq!(matrix.get([1, 1, 1]), Some(&7));
+}
+
+#[test]
+fn matrix_remove() {
+    let mut matrix = GraphMat::new();
+
+    matrix.set([0, 0, 0], 1);
+    matrix.set([1, 1, 1], 2);
+    matrix.set([1, 0, 1], 3);
+    matrix.set([-1, -1, -1], 4);
+
+    assert_eq!(matrix.remove([1, 1, 0]), None);
+    assert_eq!(matrix.remove([0, 0, 0]), Some(1));
+    assert_eq!(matrix.remove([0, 0, 0]), None);
+
+    // The leader is now a placeholder, but still required to reach the rest of the block
+    assert_eq!(matrix.get([1, 1, 1]), Some(&2));
+    assert_eq!(matrix.remove([1, 1, 1]), Some(2));
+    assert_eq!(matrix.get([1, 0, 1]), Some(&3));
+
+    matrix.free_pos([1, 0, 1]);
+    matrix.free_all(|n| *n == 4);
+    assert_eq!(matrix.get([-1, -1, -1]), None);
+
+    // Nothing is left, so all placeholders and leaders must have been freed too
+    assert_eq!(matrix.arena.len(), 0);
+}
+
+#[test]
+fn matrix_len() {
+    let mut matrix = GraphMat::new();
+    assert!(matrix.is_empty());
+
+    matrix.set([1, 1, 1], 1);
+    matrix.set([1, 1, 1], 2);
+    *matrix.entry([2, 2, 2]).or_insert(0) += 1;
+    *matrix.entry([2, 2, 2]).or_insert(0) += 1;
+    matrix.set([-5, 0, 3], 3);
+    assert_eq!(matrix.len(), 3);
+    assert_eq!(matrix.node_count(), 3);
+
+    // [1, 1, 1] needs the leader, [1, 0, 0] and [1, 1, 0] as placeholders, [-5, 0, 3] needs the leader and [-5, 0, 2]
+    assert_eq!(matrix.allocated_nodes(), 3 + 5);
+
+    matrix.remove([1, 1, 1]);
+    matrix.remove([1, 1, 1]);
+    matrix.free_pos([0, 0, 0]);
+    assert_eq!(matrix.len(), 2);
+    assert_eq!(matrix.node_count(), 2);
+
+    matrix.free_all(|_| true);
+    assert!(matrix.is_empty());
+    assert_eq!(matrix.allocated_nodes(), 0);
+
+    // Reserving less than what is already allocated does nothing
+    for i in 0..100 {
+        matrix.set([10 * i, 0, 0], i);
+    }
+    matrix.reserve(10);
+    assert_eq!(matrix.len(), 100);
+}
+
+#[test]
+fn matrix_whole_iterators() {
+    let mut matrix = GraphMat::new();
+
+    for i in -3..3 {
+        matrix.set([i, 2 * i, 1], i);
+    }
+    matrix.set([5, 5, 5], 0);
+    matrix.remove([5, 5, 5]);
+
+    // Placeholder nodes must not be yielded
+    assert_eq!(matrix.iter().len(), 6);
+    assert_eq!(matrix.values().sum::<i32>(), -3);
+
+    let mut keys: Vec<_> = matrix.keys().collect();
+    keys.sort();
+    assert_eq!(keys[0], [-3, -6, 1]);
+
+    for (coord, value) in &mut matrix {
+        *value += coord[1];
+    }
+    matrix.values_mut().for_each(|value| *value *= 2);
+    assert_eq!(matrix.get([2, 4, 1]), Some(&12));
+
+    let mut it = (&matrix).into_iter().filter(|(_, value)| **value > 0);
+    assert!(it.next().is_some());
+
+    let mut cells: Vec<_> = matrix.into_iter().collect();
+    cells.sort();
+    assert_eq!(cells.len(), 6);
+    assert_eq!(cells[0], ([-3, -6, 1], -18));
+}
+
+#[test]
+fn matrix_ref_iterators() {
+    let mut matrix = GraphMat::new();
+
+    for i in 0..10 {
+        matrix.set([i, 3, 5], i);
+        matrix.set([3, i, 6], 10 * i);
+        matrix.set([7, 7, i], 100 * i);
+    }
+
+    // Multiple iterators over the same matrix at once
+    let east = matrix.iter_dir_ref::<markers::Purva>([0, 3, 5]);
+    let north = matrix.iter_dir_ref::<markers::Uttar>([3, 0, 6]);
+    let sky: Vec<_> = matrix.iter_dir_ref::<markers::Urdhwa>([7, 7, 0]).take_while(|(_, v)| **v < 500).collect();
+
+    assert_eq!(east.map(|(_, v)| *v).collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+    assert_eq!(north.count(), 10);
+    assert_eq!(sky.len(), 5);
+    assert_eq!(sky[4], ([7, 7, 4], &400));
+
+    // Stops at the first cell without data
+    assert_eq!(matrix.iter_dir_ref::<markers::Paschim>([9, 3, 5]).count(), 10);
+    assert_eq!(matrix.iter_dir_ref::<markers::Ishanya>([2, 2, 5]).count(), 0);
+
+    for i in 6..10 {
+        matrix.set([3, 9, i], 1000 * i);
+    }
+
+    let mut it = matrix.iter_all_dir_ref([3, 0, 6], Direction::uttar);
+    let mut visited = Vec::new();
+    while let Some((coord, _)) = it.next() {
+        visited.push(coord);
+        if coord == [3, 9, 6] {
+            it.set_direction(Direction::urdhwa);
+        }
+    }
+    assert_eq!(visited.len(), 10 + 3);
+    assert_eq!(visited.last(), Some(&[3, 9, 9]));
+}
+
+#[test]
+fn matrix_iterator_mut() {
+    let mut matrix = GraphMat::new();
+
+    for i in 0..10 {
+        matrix.set([i, 1, 1], i);
+    }
+    for j in 2..6 {
+        matrix.set([9, j, 1], 0);
+    }
+
+    // Shift every value along x by one cell, ie. each cell takes the value of its west neighbour
+    let mut carry = -1;
+    matrix.iter_dir::<markers::Purva>([0, 1, 1]).for_each_mut(|_, val| {
+        carry = std::mem::replace(val, carry);
+    });
+    assert_eq!(matrix.get([0, 1, 1]), Some(&-1));
+    assert_eq!(matrix.get([9, 1, 1]), Some(&8));
+
+    // Turning while walking
+    let mut it = matrix.iter_all_dir([5, 1, 1], Direction::purva);
+    while let Some((coord, val)) = it.next_mut() {
+        *val += 100;
+        if coord == [9, 1, 1] {
+            it.set_direction(Direction::uttar);
+        }
+    }
+    assert_eq!(matrix.get([4, 1, 1]), Some(&3));
+    assert_eq!(matrix.get([5, 1, 1]), Some(&104));
+    assert_eq!(matrix.get([9, 1, 1]), Some(&108));
+    assert_eq!(matrix.get([9, 5, 1]), Some(&100));
+}
+
+// Behaviour of the mutably borrowing directional iterators, that changed when they were made to share one stepping function
+#[test]
+fn matrix_iterator_steps() {
+    let mut matrix = GraphMat::new();
+    for x in 0..6 {
+        matrix.set([x, 1, 1], x);
+    }
+
+    // [0, 1, 1] has no east link, [1, 1, 1] is reached from the leader through [1, 0, 0], this used to stop after one cell
+    let mut it = matrix.iter_dir::<markers::Purva>([0, 1, 1]);
+    let mut visited = Vec::new();
+    while let Some((coord, _)) = it.next() {
+        visited.push(coord);
+    }
+    assert_eq!(visited, (0..6).map(|x| [x, 1, 1]).collect::<Vec<_>>());
+
+    // Upwards moves along z, this used to move along x
+    for z in 2..5 {
+        matrix.set([0, 1, z], 10 * z);
+    }
+    let mut it = matrix.iter_dir::<markers::Urdhwa>([0, 1, 1]);
+    let mut visited = Vec::new();
+    while let Some((coord, _)) = it.next() {
+        visited.push(coord);
+    }
+    assert_eq!(visited, vec![[0, 1, 1], [0, 1, 2], [0, 1, 3], [0, 1, 4]]);
+
+    // The turn takes effect right after the node last returned, this used to go one more node east first
+    for y in 2..4 {
+        matrix.set([2, y, 1], 100 * y);
+    }
+    let mut it = matrix.iter_all_dir([0, 1, 1], Direction::purva);
+    let mut visited = Vec::new();
+    while let Some((coord, _)) = it.next() {
+        visited.push(coord);
+        if coord == [2, 1, 1] {
+            assert_eq!(it.set_direction(Direction::uttar), Direction::purva);
+        }
+    }
+    assert_eq!(visited, vec![[0, 1, 1], [1, 1, 1], [2, 1, 1], [2, 2, 1], [2, 3, 1]]);
+}
+
+#[test]
+fn matrix_bulk_construction() {
+    let mut matrix: GraphMat<i32> = (0..10).map(|i| ([i, -i, i % 3], i)).collect();
+    assert_eq!(matrix.len(), 10);
+    assert_eq!(matrix.get([7, -7, 1]), Some(&7));
+
+    matrix.extend([([7, -7, 1], 70), ([100, 100, 100], 1), ([100, 100, 100], 2)]);
+    assert_eq!(matrix.len(), 11);
+    assert_eq!(matrix.get([7, -7, 1]), Some(&70));
+    assert_eq!(matrix.get([100, 100, 100]), Some(&2));
+
+    let dense: Vec<Vec<Vec<i32>>> = (0..5)
+        .map(|i| (0..4).map(|j| (0..3).map(|k| i * 100 + j * 10 + k).collect()).collect())
+        .collect();
+    let matrix = GraphMat::from_dense(&dense, [-2, -1, 7]);
+
+    assert_eq!(matrix.len(), 5 * 4 * 3);
+    assert_eq!(matrix.get([-2, -1, 7]), Some(&0));
+    assert_eq!(matrix.get([2, 2, 9]), Some(&432));
+    assert_eq!(matrix.get([3, 2, 9]), None);
+
+    // Only the nodes required to reach the 60 cells, nothing thrown away in between
+    let mut expected = GraphMat::new();
+    for (coord, data) in matrix.iter() {
+        expected.set(coord, *data);
+    }
+    assert_eq!(matrix.allocated_nodes(), expected.allocated_nodes());
+}
+
+#[test]
+fn matrix_std_traits() {
+    let mut matrix = GraphMat::default();
+    matrix.set([1, 0, 1], 'a');
+    matrix.set([-4, 2, 0], 'b');
+
+    matrix[[1, 0, 1]] = 'c';
+    assert_eq!(matrix[[1, 0, 1]], 'c');
+    assert_eq!(format!("{:?}", matrix.clone()).len(), "{[1, 0, 1]: 'c', [-4, 2, 0]: 'b'}".len());
+
+    // Same cells, but different arena layout and a placeholder left behind
+    let mut other = GraphMat::new();
+    other.set([1, 1, 1], 'z');
+    other.set([-4, 2, 0], 'b');
+    other.set([1, 0, 1], 'c');
+    other.remove([1, 1, 1]);
+    assert_eq!(matrix, other);
+
+    let mut cloned = matrix.clone();
+    cloned[[-4, 2, 0]] = 'd';
+    assert_ne!(matrix, cloned);
+    assert_eq!(matrix[[-4, 2, 0]], 'b');
+}
+
+#[test]
+#[should_panic(expected = "[3, 3, 3]")]
+fn matrix_index_panics() {
+    let matrix: GraphMat<i32> = GraphMat::new();
+    let _ = matrix[[3, 3, 3]];
+}
+
+#[test]
+fn matrix_coord_types() {
+    let mut large: GraphMat<&str, i64> = GraphMat::new();
+    let far = [i64::MAX, i64::MIN, -5_000_000_000];
+    large.set(far, "far");
+    large.set([i64::MIN + 1, 3, -3], "near min");
+    assert_eq!(large.get(far), Some(&"far"));
+    assert_eq!(large[[i64::MIN + 1, 3, -3]], "near min");
+    assert_eq!(large.get([i64::MIN, 3, -3]), None);
+
+    let mut small: GraphMat<i32, i16> = (-20..20).map(|i| ([i, -i, i16::MIN + 1], i32::from(i))).collect();
+    assert_eq!(small.len(), 40);
+    assert_eq!(small.get([-7, 7, i16::MIN + 1]), Some(&-7));
+    assert_eq!(small.remove([-19, 19, i16::MIN + 1]), Some(-19));
+
+    let mut unsigned: GraphMat<u32, u32> = GraphMat::new();
+    for x in 0..5 {
+        unsigned.set([x, 0, 1], x);
+    }
+    // Going west from x = 0 must stop, instead of wrapping around
+    let west: Vec<_> = unsigned.iter_dir_ref::<markers::Paschim>([4, 0, 1]).collect();
+    assert_eq!(west.len(), 5);
+    assert_eq!(unsigned.iter_dir_ref::<markers::Dakshin>([2, 0, 1]).count(), 1);
+}
+
+#[test]
+fn matrix_other_dimensions() {
+    let mut map: GraphMat2D<char> = GraphMat::new();
+    for x in -5..5 {
+        for y in -5..5 {
+            if (x + y) % 3 == 0 {
+                map.set([x, y], '#');
+            }
+        }
+    }
+    assert_eq!(map.get([-3, 3]), Some(&'#'));
+    assert_eq!(map.get([-3, 2]), None);
+    assert_eq!(map.iter().filter(|([x, _], _)| *x == 0).count(), 3);
+
+    let mut spacetime: GraphMat4D<u8, i64> = GraphMat::new();
+    spacetime.set([1, 1, 1, 1], 1);
+    spacetime.set([-1, 0, -1, 7], 2);
+    // [1, 1, 1, 1] is reached from its leader through one link per axis
+    assert_eq!(spacetime.allocated_nodes(), 5 + 4);
+    assert_eq!(spacetime[[-1, 0, -1, 7]], 2);
+
+    assert_eq!(spacetime.remove([1, 1, 1, 1]), Some(1));
+    assert_eq!(spacetime.remove([-1, 0, -1, 7]), Some(2));
+    assert_eq!(spacetime.allocated_nodes(), 0);
+}
+
+#[test]
+fn matrix_block_sizes() {
+    for block_size in [1, 2, 3, 4, 8] {
+        let mut matrix = GraphMat::with_block_size(block_size);
+        for i in -10..10 {
+            for j in -10..10 {
+                matrix.set([i, j, (i * j) % 7], i + j);
+            }
+        }
+
+        assert_eq!(matrix.block_size(), block_size);
+        assert_eq!(matrix.len(), 400);
+        assert_eq!(matrix.get([-7, 3, -21 % 7]), Some(&-4));
+        assert_eq!(matrix.get([-7, 3, 1]), None);
+        assert_eq!(matrix.iter_dir_ref::<markers::Purva>([-10, 0, 0]).count(), 20);
+
+        for i in -10..10 {
+            for j in -10..10 {
+                assert_eq!(matrix.remove([i, j, (i * j) % 7]), Some(i + j));
+            }
+        }
+        assert_eq!(matrix.allocated_nodes(), 0);
+    }
+
+    // Within a block of 4, [3, 0, 2] is reached by 3 links east then 2 skywards
+    let mut matrix = GraphMat::with_block_size(4);
+    matrix.set([3, 0, 2], ());
+    assert_eq!(matrix.allocated_nodes(), 1 + 3 + 2);
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+#[should_panic(expected = "Block size must be at most i64::MAX")]
+fn matrix_block_size_too_large() {
+    let _: GraphMat<u8> = GraphMat::with_block_size(usize::MAX);
+}
+
+#[test]
+fn matrix_block_size_not_dividing_min() {
+    // Neither -128 nor i64::MIN is a multiple of 3, the blocks start from there anyway
+    let mut matrix: GraphMat<u8, i8> = GraphMat::with_block_size(3);
+    for x in i8::MIN..=i8::MAX {
+        matrix.set([x, 0, i8::MIN], x as u8);
+    }
+    assert_eq!(matrix.get([-128, 0, -128]), Some(&128));
+    assert_eq!(matrix.get([127, 0, -128]), Some(&127));
+    assert_eq!(matrix.iter_region([-128, -128, -128], [-126, 0, -128]).count(), 3);
+    assert_eq!(matrix.iter_dir_ref::<markers::Purva>([-128, 0, -128]).count(), 256);
+    assert_eq!(matrix.within_chebyshev([0, 0, 0], 200).count(), 256);
+
+    let mut matrix: GraphMat<u8, i64> = GraphMat::with_block_size(3);
+    for (i, x) in [i64::MIN, i64::MIN + 1, i64::MIN + 3, 0, i64::MAX].into_iter().enumerate() {
+        matrix.set([x, i64::MIN, i64::MAX], i as u8);
+    }
+    assert_eq!(matrix.get([i64::MIN, i64::MIN, i64::MAX]), Some(&0));
+    assert_eq!(matrix.get([i64::MAX, i64::MIN, i64::MAX]), Some(&4));
+    assert_eq!(matrix.iter_region([i64::MIN; 3], [i64::MAX; 3]).count(), 5);
+    assert_eq!(matrix.remove([i64::MIN, i64::MIN, i64::MAX]), Some(0));
+    assert_eq!(matrix.len(), 4);
+}
+
+#[cfg(feature = "nightly")]
+#[test]
+fn matrix_const_direction() {
+    use markers::ConstDir;
+
+    let mut matrix = GraphMat::new();
+    for i in 0..5 {
+        matrix.set([0, 0, i], i);
+    }
+
+    assert_eq!(matrix.iter_dir_ref::<ConstDir<{ Direction::urdhwa }>>([0, 0, 0]).count(), 5);
+    assert_eq!(
+        matrix.iter_dir_ref::<ConstDir<{ Direction::adharastha }>>([0, 0, 4]).count(),
+        matrix.iter_dir_ref::<markers::Adharastha>([0, 0, 4]).count()
+    );
+}
+
+#[test]
+fn matrix_bounds() {
+    // The lazily recomputed bounds must not stop the matrix from being shared between threads
+    fn assert_sync<S: Sync + Send>() {}
+    assert_sync::<GraphMat<i32>>();
+    assert_sync::<GraphMat2D<u8, i64>>();
+
+    let mut matrix = GraphMat::new();
+    assert_eq!(matrix.bounds(), None);
+    assert_eq!(matrix.min_x(), None);
+
+    matrix.set([1, 2, 3], 'a');
+    assert_eq!(matrix.bounds(), Some(([1, 2, 3], [1, 2, 3])));
+
+    matrix.set([-4, 5, 0], 'b');
+    *matrix.entry([2, -1, 7]).or_insert('c') = 'd';
+    assert_eq!(matrix.bounds(), Some(([-4, -1, 0], [2, 5, 7])));
+    assert_eq!((matrix.min_x(), matrix.max_x()), (Some(-4), Some(2)));
+    assert_eq!((matrix.min_y(), matrix.max_y()), (Some(-1), Some(5)));
+    assert_eq!((matrix.min_z(), matrix.max_z()), (Some(0), Some(7)));
+
+    // Removing a cell inside the box doesn't change it, removing one on the boundary shrinks it
+    matrix.set([0, 0, 1], 'e');
+    matrix.remove([0, 0, 1]);
+    assert_eq!(matrix.bounds(), Some(([-4, -1, 0], [2, 5, 7])));
+
+    matrix.remove([2, -1, 7]);
+    assert_eq!(matrix.bounds(), Some(([-4, 2, 0], [1, 5, 3])));
+    assert_eq!(matrix.clone().bounds(), matrix.bounds());
+
+    matrix.remove([1, 2, 3]);
+    matrix.remove([-4, 5, 0]);
+    assert_eq!(matrix.bounds(), None);
+
+    let plane: GraphMat2D<u8, u16> = [([3, 9], 1), ([7, 4], 2)].into_iter().collect();
+    assert_eq!(plane.bounds(), Some(([3, 4], [7, 9])));
+    assert_eq!(plane.min_along(1), Some(4));
+}
+
+#[test]
+fn matrix_region() {
+    let mut matrix = GraphMat::new();
+    for x in -5..5 {
+        for y in -5..5 {
+            matrix.set([x, y, x + y], x * 10 + y);
+        }
+    }
+
+    let mut cells: Vec<_> = matrix.iter_region([-1, 0, -10], [1, 1, 10]).map(|(coord, data)| (coord, *data)).collect();
+    cells.sort();
+    assert_eq!(
+        cells,
+        vec![([-1, 0, -1], -10), ([-1, 1, 0], -9), ([0, 0, 0], 0), ([0, 1, 1], 1), ([1, 0, 1], 10), ([1, 1, 2], 11)]
+    );
+
+    // Same result whether the leaders are looked up one by one, or picked from all the allocated ones
+    let everything = matrix.iter_region([i32::MIN + 1, i32::MIN + 1, i32::MIN + 1], [i32::MAX - 1, i32::MAX - 1, i32::MAX - 1]);
+    assert_eq!(everything.count(), 100);
+
+    // Over the whole range of i64, the number of leader positions doesn't fit an i64
+    let mut wide: GraphMat<u8, i64> = GraphMat::new();
+    wide.set([i64::MIN, 0, i64::MAX], 1);
+    wide.set([0, 0, 0], 2);
+    wide.set([i64::MAX, i64::MIN, 5], 3);
+    assert_eq!(wide.iter_region([i64::MIN; 3], [i64::MAX; 3]).count(), 3);
+    assert_eq!(wide.within_manhattan([0, 0, 0], u64::MAX).count(), 3);
+    assert_eq!(wide.within_chebyshev([0, 0, 0], u64::MAX).count(), 3);
+    assert_eq!(wide.within_radius([0, 0, 0], f64::MAX).count(), 3);
+    assert_eq!(wide.within_radius([i64::MAX, i64::MIN, 0], 5.0).map(|(_, data)| *data).collect::<Vec<_>>(), vec![3]);
+    assert_eq!(matrix.iter_region([0, 0, 1], [0, 0, 1]).count(), 0);
+    assert_eq!(matrix.iter_region([1, 0, 0], [0, 0, 0]).count(), 0);
+
+    matrix.iter_region_mut([3, 3, 0], [10, 10, 10]).for_each_mut(|_, data| *data = 1000);
+    assert_eq!(matrix.values().filter(|data| **data == 1000).count(), 4);
+    assert_eq!(matrix.get([4, 4, 8]), Some(&1000));
+
+    let mut region = matrix.iter_region_mut([4, 4, 8], [4, 4, 8]);
+    *region.next_mut().unwrap().1 = 7;
+    assert!(region.next().is_none());
+    assert_eq!(matrix[[4, 4, 8]], 7);
+
+    let mut plane: GraphMat2D<i32> = GraphMat::with_block_size(3);
+    plane.extend((0..20).map(|i| ([i, -i], i)));
+    assert_eq!(plane.iter_region([5, -12], [15, 0]).map(|(_, data)| *data).sum::<i32>(), (5..=12).sum());
+}
+
+#[test]
+fn matrix_radius_queries() {
+    let mut matrix = GraphMat::new();
+    for x in -6..=6 {
+        for y in -6..=6 {
+            for z in -6..=6 {
+                matrix.set([x, y, z], ());
+            }
+        }
+    }
+
+    // Each query must find the same cells as checking every cell against the distance
+    let center = [1, -2, 0];
+    let brute = |within: &dyn Fn([i32; 3]) -> bool| matrix.keys().filter(|coord| within(*coord)).count();
+    let diff = |coord: [i32; 3]| [0, 1, 2].map(|axis| (coord[axis] - center[axis]).abs());
+
+    assert_eq!(
+        matrix.within_radius(center, 3.5).count(),
+        brute(&|coord| diff(coord).iter().map(|d| d * d).sum::<i32>() as f64 <= 3.5 * 3.5)
+    );
+    assert_eq!(matrix.within_radius(center, 5.0).count(), brute(&|coord| diff(coord).iter().map(|d| d * d).sum::<i32>() <= 25));
+    assert_eq!(matrix.within_manhattan(center, 4).count(), brute(&|coord| diff(coord).iter().sum::<i32>() <= 4));
+    assert_eq!(matrix.within_chebyshev(center, 2).count(), 5 * 5 * 5);
+
+    assert_eq!(matrix.within_radius(center, 0.0).map(|(coord, _)| coord).collect::<Vec<_>>(), vec![center]);
+    assert_eq!(matrix.within_radius(center, -1.0).count(), 0);
+    assert_eq!(matrix.within_radius([100, 100, 100], 10.0).count(), 0);
+
+    // Clamped to the range of the coordinate type
+    let corner: GraphMat2D<(), u8> = [([0, 0], ()), ([1, 1], ()), ([255, 255], ())].into_iter().collect();
+    assert_eq!(corner.within_manhattan([0, 0], 1000).count(), 3);
+    assert_eq!(corner.within_radius([0, 0], 1.5).count(), 2);
+}
+
+#[test]
+fn matrix_nearest() {
+    let empty: GraphMat<i32> = GraphMat::new();
+    assert_eq!(empty.nearest([0, 0, 0]), None);
+
+    // Pseudo-random cells, the results must match checking the distance to every cell
+    let mut seed: i64 = 42;
+    let mut random = move |range: i64| {
+        seed = (seed * 1103515245 + 12345) % (1 << 31);
+        (seed % range) as i32 - (range / 2) as i32
+    };
+
+    for block_size in [1, 2, 5] {
+        let mut matrix = GraphMat::with_block_size(block_size);
+        for i in 0..300 {
+            matrix.set([random(200), random(200), random(60)], i);
+        }
+        matrix.set([1000, 1000, 1000], -1);
+
+        for query in [[0, 0, 0], [random(300), random(300), random(300)], [2000, -2000, 0]] {
+            let distance = |coord: [i32; 3]| {
+                (0..3).map(|axis| ((coord[axis] - query[axis]) as f64).powi(2)).sum::<f64>().sqrt()
+            };
+            let mut all: Vec<f64> = matrix.keys().map(distance).collect();
+            all.sort_by(f64::total_cmp);
+
+            let (coord, d) = matrix.nearest(query).unwrap();
+            assert_eq!((d, distance(coord)), (all[0], all[0]));
+
+            let k_nearest: Vec<f64> = matrix.k_nearest(query, 10).into_iter().map(|(_, d)| d).collect();
+            assert_eq!(k_nearest, all[..10]);
+            assert_eq!(matrix.k_nearest(query, 500).len(), matrix.len());
+
+            let mut odd: Vec<f64> = matrix.iter().filter(|(_, i)| *i % 2 == 1).map(|(coord, _)| distance(coord)).collect();
+            odd.sort_by(f64::total_cmp);
+            assert_eq!(matrix.nearest_where(query, |i| i % 2 == 1).map(|(_, d)| d), Some(odd[0]));
+        }
+
+        assert_eq!(matrix.nearest_where([0, 0, 0], |i| *i < 0), Some(([1000, 1000, 1000], 1000.0 * 3f64.sqrt())));
+        assert_eq!(matrix.nearest_where([0, 0, 0], |i| *i > 1000), None);
+    }
+
+    let plane: GraphMat2D<()> = [([3, 4], ()), ([-10, 0], ())].into_iter().collect();
+    assert_eq!(plane.nearest([0, 0]), Some(([3, 4], 5.0)));
+    assert!(plane.k_nearest([0, 0], 0).is_empty());
+}
+
+#[test]
+fn matrix_all_directions() {
+    assert_eq!(Direction::all().count(), 26);
+    assert_eq!((Direction::face().count(), Direction::edge().count(), Direction::corner().count()), (6, 12, 8));
+
+    for dir in Direction::all() {
+        assert_eq!(Direction::from_offset(dir.offset()), Some(dir));
+        assert_eq!(dir.opposite().opposite(), dir);
+        assert_ne!(dir.opposite(), dir);
+    }
+    assert_eq!(Direction::from_offset([0, 0, 0]), None);
+    assert_eq!(Direction::from_offset([2, 0, 0]), None);
+    assert_eq!(Direction::urdhwa_ishanya.offset(), [1, 1, 1]);
+    assert_eq!(Direction::UP_NORTH_EAST.opposite(), Direction::DOWN_SOUTH_WEST);
+    assert_eq!(Direction::NORTH, Direction::uttar);
+
+    // Both kinds of iterators move along the new directions
+    let mut matrix = GraphMat::new();
+    for i in 0..4 {
+        matrix.set([i, -i, 10 - i], i);
+    }
+
+    let values: Vec<_> = matrix.iter_dir_ref::<markers::DownSouthEast>([0, 0, 10]).map(|(_, data)| *data).collect();
+    assert_eq!(values, vec![0, 1, 2, 3]);
+    let values: Vec<_> = matrix.iter_all_dir_ref([3, -3, 7], Direction::urdhwa_vayavya).map(|(_, data)| *data).collect();
+    assert_eq!(values, vec![3, 2, 1, 0]);
+
+    let mut it = matrix.iter_all_dir([1, -1, 9], Direction::UP_NORTH_WEST);
+    let mut visited = Vec::new();
+    while let Some((coord, _)) = it.next_mut() {
+        visited.push(coord);
+    }
+    assert_eq!(visited, vec![[1, -1, 9], [0, 0, 10]]);
+}
+
+#[test]
+fn matrix_neighbours() {
+    let mut matrix = GraphMat::new();
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                matrix.set([x + 5, y, z], x * 100 + y * 10 + z);
+            }
+        }
+    }
+
+    // Crossing into the adjacent blocks on every side, ie. [5, 0, 0] is the first cell of its block
+    let center = [5, 0, 0];
+    assert_eq!(matrix.neighbours(center, Connectivity::Six).count(), 6);
+    assert_eq!(matrix.neighbours(center, Connectivity::Eighteen).count(), 18);
+    assert_eq!(matrix.neighbours(center, Connectivity::TwentySix).count(), 26);
+
+    for (dir, coord, data) in matrix.neighbours(center, Connectivity::TwentySix) {
+        let [x, y, z] = dir.offset();
+        assert_eq!(coord, [5 + x, y, z]);
+        assert_eq!(*data, x * 100 + y * 10 + z);
+    }
+
+    matrix.remove([5, 0, 1]);
+    matrix.remove([4, -1, -1]);
+    let dirs: Vec<_> = matrix.neighbours(center, Connectivity::TwentySix).map(|(dir, _, _)| dir).collect();
+    assert_eq!(dirs.len(), 24);
+    assert!(!dirs.contains(&Direction::urdhwa) && !dirs.contains(&Direction::adharastha_nairutya));
+
+    // Works the same whether or not the center itself has some data
+    matrix.remove(center);
+    assert_eq!(matrix.neighbours(center, Connectivity::Six).count(), 5);
+    assert_eq!(matrix.neighbours([7, 0, 0], Connectivity::TwentySix).count(), 9);
+    assert_eq!(matrix.neighbours([50, 0, 0], Connectivity::TwentySix).count(), 0);
+
+    let edge: GraphMat<(), u8> = [([0, 0, 0], ()), ([1, 0, 0], ())].into_iter().collect();
+    assert_eq!(edge.neighbours([0, 0, 0], Connectivity::TwentySix).count(), 1);
+}
+
+#[test]
+fn matrix_raycast() {
+    let mut matrix = GraphMat::new();
+    for x in 0..10 {
+        matrix.set([x, 0, 0], x);
+    }
+    matrix.set([5, 5, 5], 55);
+    matrix.set([-3, 2, 0], -32);
+
+    // Along +x from inside cell 2, entering each next cell through its west face
+    let hits: Vec<_> = matrix.raycast([2.5, 0.5, 0.5], [1.0, 0.0, 0.0], 4.0).collect();
+    assert_eq!(hits.iter().map(|hit| *hit.data).collect::<Vec<_>>(), vec![2, 3, 4, 5, 6]);
+    assert_eq!(hits[0].face, None);
+    assert_eq!(hits[1].face, Some(Direction::paschim));
+    assert_eq!(hits[1].distance, 0.5);
+
+    // Diagonal through the main diagonal of the cube, and the length of `dir` doesn't matter
+    let hit = matrix.first_hit([0.5, 0.5, 0.5], [3.0, 3.0, 3.0], 100.0).unwrap();
+    assert_eq!((hit.coord, *hit.data), ([0, 0, 0], 0));
+    let hit = matrix.first_hit([1.5, 1.5, 1.5], [1.0, 1.0, 1.0], 100.0).unwrap();
+    assert_eq!(hit.coord, [5, 5, 5]);
+    assert!(hit.face.is_some_and(|face| Direction::face().any(|dir| dir == face)));
+
+    let hit = matrix.first_hit([0.5, 2.5, 0.5], [-1.0, 0.0, 0.0], 10.0).unwrap();
+    assert_eq!((hit.coord, hit.face, hit.distance), ([-3, 2, 0], Some(Direction::purva), 2.5));
+    assert!(matrix.first_hit([0.5, 2.5, 0.5], [-1.0, 0.0, 0.0], 2.0).is_none());
+    assert!(matrix.first_hit([0.5, 0.5, 0.5], [0.0, 0.0, 0.0], 10.0).is_none());
+    assert!(matrix.first_hit([0.5, 0.5, 0.5], [0.0, 1.0, 0.0], f64::NAN).is_none());
+
+    // An infinite ray stops once it leaves the bounds, whether it missed everything or not
+    assert!(matrix.first_hit([0.5, 1.5, 0.5], [1.0, 0.0, 0.0], f64::INFINITY).is_none());
+    assert!(matrix.first_hit([100.5, 0.5, 0.5], [1.0, 0.0, 0.0], f64::INFINITY).is_none());
+    assert_eq!(matrix.raycast([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], f64::INFINITY).count(), 10);
+    let hit = matrix.first_hit([100.5, 0.5, 0.5], [-1.0, 0.0, 0.0], f64::INFINITY).unwrap();
+    assert_eq!((hit.coord, hit.distance), ([9, 0, 0], 90.5));
+    assert!(GraphMat::<i32>::new().first_hit([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], f64::INFINITY).is_none());
+
+    // From outside the range of the coordinate type, and from far away, without visiting every cell up to the bounds
+    let mut unsigned: GraphMat<u8, u32> = GraphMat::new();
+    unsigned.set([0, 0, 0], 1);
+    let hits: Vec<_> = unsigned.raycast([-0.5, 0.5, 0.5], [1.0, 0.0, 0.0], 10.0).collect();
+    assert_eq!(hits.iter().map(|hit| (hit.coord, hit.face, hit.distance)).collect::<Vec<_>>(), vec![([0, 0, 0], Some(Direction::paschim), 0.5)]);
+    assert_eq!(unsigned.first_hit([-5.0, 0.5, 0.5], [1.0, 0.0, 0.0], 10.0).map(|hit| hit.coord), Some([0, 0, 0]));
+    assert!(unsigned.first_hit([-5.0, 0.5, 0.5], [1.0, 0.0, 0.0], 4.0).is_none());
+    let hit = unsigned.first_hit([-2e8, 0.5, 0.5], [1.0, 0.0, 0.0], f64::INFINITY).unwrap();
+    assert_eq!((hit.coord, hit.face, hit.distance), ([0, 0, 0], Some(Direction::paschim), 2e8));
+    let hit = matrix.first_hit([4.5, 2e9, 0.5], [0.0, -1.0, 0.0], f64::INFINITY).unwrap();
+    assert_eq!((hit.coord, hit.face), ([4, 0, 0], Some(Direction::uttar)));
+
+    // Bresenham, from one end to the other, both inclusive
+    let hits: Vec<_> = matrix.line([9, 0, 0], [0, 0, 0]).map(|hit| *hit.data).collect();
+    assert_eq!(hits, (0..10).rev().collect::<Vec<_>>());
+
+    let mut diagonal = GraphMat::new();
+    let cells = [[0, 0, 0], [1, 1, 1], [2, 1, 1], [3, 2, 2], [4, 2, 2]];
+    for cell in cells {
+        diagonal.set(cell, ());
+    }
+    diagonal.set([1, 1, 0], ());
+    let line: Vec<_> = diagonal.line([0, 0, 0], [4, 2, 2]).collect();
+    assert_eq!(line.iter().map(|hit| hit.coord).collect::<Vec<_>>(), cells);
+    assert_eq!(line[1].face, Some(Direction::adharastha_nairutya));
+    assert_eq!(line[2].face, Some(Direction::paschim));
+    assert_eq!(line[4].distance, 24f64.sqrt());
+    assert_eq!(diagonal.line([4, 2, 2], [4, 2, 2]).count(), 1);
+}
+
+#[test]
+fn matrix_components() {
+    let mut matrix = GraphMat::new();
+    // A room of 3x3x1, a diagonal pair touching only at a corner, and a single cell far away
+    for x in 0..3 {
+        for y in 0..3 {
+            matrix.set([x, y, 0], 'r');
+        }
+    }
+    matrix.set([10, 10, 10], 'd');
+    matrix.set([11, 11, 11], 'd');
+    matrix.set([-50, 0, 7], 's');
+
+    let mut sizes: Vec<_> = matrix.connected_components(Connectivity::Six).iter().map(Vec::len).collect();
+    sizes.sort();
+    assert_eq!(sizes, vec![1, 1, 1, 9]);
+
+    let mut sizes: Vec<_> = matrix.connected_components(Connectivity::TwentySix).iter().map(Vec::len).collect();
+    sizes.sort();
+    assert_eq!(sizes, vec![1, 2, 9]);
+
+    let labels = matrix.label_components(Connectivity::TwentySix);
+    assert_eq!(labels.len(), matrix.len());
+    assert_eq!(labels[[10, 10, 10]], labels[[11, 11, 11]]);
+    assert_eq!(labels[[0, 0, 0]], labels[[2, 2, 0]]);
+    assert_ne!(labels[[0, 0, 0]], labels[[-50, 0, 7]]);
+
+    // Only through the cells satisfying the predicate, the seed comes first
+    matrix.set([1, 0, 0], 'w');
+    matrix.set([1, 1, 0], 'w');
+    matrix.set([1, 2, 0], 'w');
+    let left = matrix.flood_fill([0, 0, 0], Connectivity::Six, |c| *c == 'r');
+    assert_eq!(left[0], [0, 0, 0]);
+    assert_eq!(left.len(), 3);
+    assert_eq!(matrix.flood_fill([0, 0, 0], Connectivity::Eighteen, |c| *c == 'r').len(), 3);
+    assert_eq!(matrix.flood_fill([0, 0, 0], Connectivity::Six, |_| true).len(), 9);
+    assert!(matrix.flood_fill([1, 0, 0], Connectivity::Six, |c| *c == 'r').is_empty());
+    assert!(matrix.flood_fill([5, 5, 5], Connectivity::Six, |_| true).is_empty());
+}
+
+#[test]
+fn matrix_pathfinding() {
+    use pathfinding::{Algorithm, PathOptions};
+
+    // A corridor along x with a wall at x = 5 except for a gap at y = 2, and a costly shortcut at y = 1
+    let mut matrix = GraphMat::new();
+    for x in 0..10 {
+        for y in 0..3 {
+            matrix.set([x, y, 0], if x == 5 && y != 2 { f64::INFINITY } else { 1.0 });
+        }
+    }
+    matrix.set([5, 1, 0], 10.0);
+
+    let passable = |cost: &f64| cost.is_finite();
+    let path = |start, goal, connectivity, options| matrix.shortest_path(start, goal, connectivity, passable, |cost| *cost, options);
+
+    let bfs = path([0, 0, 0], [9, 0, 0], Connectivity::Six, PathOptions { algorithm: Algorithm::Bfs, ..Default::default() }).unwrap();
+    assert_eq!(bfs.cells.len(), 12);
+    assert_eq!(bfs.cost, 20.0);
+
+    let dijkstra = path([0, 0, 0], [9, 0, 0], Connectivity::Six, PathOptions::default()).unwrap();
+    assert_eq!((dijkstra.cells.len(), dijkstra.cost), (14, 13.0));
+    assert!(dijkstra.cells.contains(&[5, 2, 0]));
+    assert_eq!((dijkstra.cells[0], dijkstra.cells[13]), ([0, 0, 0], [9, 0, 0]));
+
+    let heuristic = pathfinding::euclidean;
+    let a_star = path([0, 0, 0], [9, 0, 0], Connectivity::Six, PathOptions { algorithm: Algorithm::AStar(&heuristic), ..Default::default() });
+    assert_eq!(a_star.map(|path| path.cost), Some(13.0));
+
+    // Diagonal steps cost more than 1
+    let diagonal = path([4, 1, 0], [6, 1, 0], Connectivity::Eighteen, PathOptions::default()).unwrap();
+    assert_eq!(diagonal.cells, vec![[4, 1, 0], [5, 2, 0], [6, 1, 0]]);
+    assert_eq!(diagonal.cost, 2.0 * 2f64.sqrt());
+
+    assert!(path([0, 0, 0], [0, 0, 0], Connectivity::Six, PathOptions::default()).is_some_and(|path| path.cells.len() == 1));
+    assert_eq!(path([0, 0, 0], [20, 0, 0], Connectivity::Six, PathOptions::default()), None);
+
+    // Through the empty cells, going around the whole corridor since none of it is passable
+    let around = matrix.shortest_path(
+        [0, -1, 0],
+        [9, -1, 0],
+        Connectivity::Six,
+        |_| false,
+        |_| 1.0,
+        PathOptions { through_empty: true, ..Default::default() },
+    );
+    assert_eq!(around.map(|path| path.cost), Some(9.0));
+
+    let enclosed = matrix.shortest_path(
+        [0, -1, 0],
+        [5, 1, 1],
+        Connectivity::Six,
+        |_| false,
+        |_| 1.0,
+        PathOptions { through_empty: true, algorithm: Algorithm::AStar(&pathfinding::manhattan) },
+    );
+    assert_eq!(enclosed.map(|path| path.cost), Some(8.0));
+    assert_eq!(
+        matrix.shortest_path([0, -1, 0], [5, 1, 0], Connectivity::Six, |_| false, |_| 1.0, PathOptions { through_empty: true, ..Default::default() }),
+        None
+    );
+}
+
+#[test]
+fn matrix_slices() {
+    let mut matrix = GraphMat::new();
+    for x in -2..3 {
+        for y in 0..4 {
+            for z in 0..3 {
+                if (x + y + z) % 2 == 0 {
+                    matrix.set([x, y, z], x * 100 + y * 10 + z);
+                }
+            }
+        }
+    }
+
+    let xy = matrix.slice(Axis::Z, 1);
+    assert_eq!(xy.get([1, 0]), Some(&101));
+    assert_eq!(xy.get([0, 0]), None);
+    assert_eq!(xy.iter().count(), 10);
+    assert!(xy.iter().all(|([x, y], data)| *data == x * 100 + y * 10 + 1));
+    assert_eq!(xy.bounds(), Some(([-2, 0], [2, 3])));
+
+    let xz = matrix.slice(Axis::Y, 3);
+    assert_eq!(xz.get([-1, 0]), Some(&-70));
+    assert!(xz.iter().all(|([x, z], data)| *data == x * 100 + 30 + z));
+    assert_eq!(xz.iter().count(), 7);
+
+    let yz = matrix.slice(Axis::X, -2);
+    assert_eq!(yz.bounds(), Some(([0, 0], [3, 2])));
+    assert_eq!(matrix.slice(Axis::X, 7).iter().count(), 0);
+    assert_eq!(matrix.slice(Axis::X, 7).bounds(), None);
+    assert_eq!(GraphMat::<i32>::new().slice(Axis::Z, 0).iter().count(), 0);
+
+    let mut slice = matrix.slice_mut(Axis::X, 0);
+    slice.for_each_mut(|[y, z], data| *data = -(y * 10 + z));
+    slice.set([9, 9], 1);
+    assert_eq!(slice.remove([0, 0]), Some(0));
+    *slice.get_mut([1, 1]).unwrap() += 1000;
+    assert_eq!(slice.iter().count(), 6);
+
+    assert_eq!(matrix[[0, 2, 2]], -22);
+    assert_eq!(matrix[[0, 1, 1]], 989);
+    assert_eq!(matrix[[0, 9, 9]], 1);
+    assert_eq!(matrix[[1, 2, 1]], 121);
+}
+
+#[test]
+fn matrix_reductions() {
+    // A small terrain, column [x, y] is filled from z = 0 upto z = x + y
+    let mut matrix = GraphMat::new();
+    for x in 0..3 {
+        for y in 0..3 {
+            for z in 0..=(x + y) {
+                matrix.set([x, y, z], z + 1);
+            }
+        }
+    }
+    matrix.set([7, 7, -5], 100);
+
+    let heights = matrix.first_along(Axis::Z, Side::Max);
+    assert_eq!(heights.len(), 10);
+    assert_eq!(heights[[2, 1]], (3, &4));
+    assert_eq!(heights[[7, 7]], (-5, &100));
+    assert_eq!(matrix.first_along(Axis::Z, Side::Min)[[2, 1]], (0, &1));
+
+    let counts = matrix.count_axis(Axis::Z);
+    assert_eq!((counts[[0, 0]], counts[[2, 2]]), (1, 5));
+    assert_eq!(matrix.sum_axis(Axis::Z)[[1, 2]], 1 + 2 + 3 + 4);
+    assert_eq!(matrix.max_axis(Axis::Z)[[2, 2]], 5);
+    assert_eq!(matrix.min_axis(Axis::Z)[[2, 2]], 1);
+
+    // Along x, the result is indexed by [y, z]
+    let rows = matrix.count_axis(Axis::X);
+    assert_eq!(rows[[0, 0]], 3);
+    assert_eq!(rows[[0, 2]], 1);
+    assert_eq!(rows.get([0, 3]), None);
+    assert_eq!(matrix.sum_axis(Axis::Y)[[7, -5]], 100);
+
+    let product = matrix.reduce_axis(Axis::Z, 1i64, |product, data| product * *data as i64);
+    assert_eq!(product[[2, 2]], 120);
+    assert!(GraphMat::<i32>::new().sum_axis(Axis::Z).is_empty());
+
+    // Each column is folded in order along the axis, even when the arena slots were reused out of order
+    let mut column = GraphMat::new();
+    for z in 0..5 {
+        column.set([0, 0, z], z);
+    }
+    column.remove([0, 0, 3]);
+    column.remove([0, 0, 1]);
+    column.set([0, 0, 8], 8);
+    column.set([0, 0, -1], -1);
+    let stacked = column.reduce_axis(Axis::Z, Vec::new(), |mut stack, data| {
+        stack.push(*data);
+        stack
+    });
+    assert_eq!(stacked[[0, 0]], vec![-1, 0, 2, 4, 8]);
+}
+
+#[test]
+fn matrix_map_and_zip() {
+    let mut matrix = GraphMat::with_block_size(3);
+    for i in -10..10 {
+        matrix.set([i, i * 2, -i], i);
+    }
+    // Leaving some placeholder nodes and freed slots in the arena
+    matrix.remove([0, 0, 0]);
+    matrix.remove([5, 10, -5]);
+
+    let strings = matrix.map(|i| i.to_string());
+    assert_eq!(strings.len(), matrix.len());
+    assert_eq!(strings.allocated_nodes(), matrix.allocated_nodes());
+    assert_eq!(strings.bounds(), matrix.bounds());
+    assert_eq!(strings[[-3, -6, 3]], "-3");
+    assert_eq!(strings.get([0, 0, 0]), None);
+    assert_eq!(strings.iter().count(), 18);
+
+    // The links are valid in the new matrix too
+    let mut strings = strings;
+    strings.set([4, 8, -3], "new".to_string());
+    assert_eq!(strings.remove([4, 8, -4]), Some("4".to_string()));
+    assert_eq!(strings.iter_region([4, 8, -4], [4, 8, -3]).map(|(_, s)| s.as_str()).collect::<Vec<_>>(), vec!["new"]);
+
+    let mut doubled = matrix.clone();
+    doubled.map_in_place(|i| i * 2);
+    assert_eq!(doubled, matrix.map(|i| i * 2));
+
+    // A panic part way leaves every cell with some value, so len() still matches the iterators
+    let mut calls = 0;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        doubled.map_in_place(|i| {
+            calls += 1;
+            if calls == 5 {
+                panic!("Stopping at the 5th cell");
+            }
+            i + 1
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(doubled.iter().count(), doubled.len());
+    assert_eq!(doubled.iter().filter(|(coord, i)| **i == matrix[*coord] * 2 + 1).count(), 4);
+
+    let mut other = GraphMat::new();
+    other.set([1, 2, -1], 100);
+    other.set([2, 4, -2], 200);
+    other.set([50, 50, 50], 5000);
+
+    let both = matrix.zip_with(&other, |a, b| a + b);
+    assert_eq!(both.len(), 2);
+    assert_eq!(both[[2, 4, -2]], 202);
+
+    let left = matrix.zip_with_left(&other, |a, b| a + b.copied().unwrap_or(0));
+    assert_eq!(left.len(), matrix.len());
+    assert_eq!((left[[1, 2, -1]], left[[3, 6, -3]]), (101, 3));
+
+    let union = matrix.zip_with_union(&other, |a, b| (a.copied(), b.copied()));
+    assert_eq!(union.len(), matrix.len() + 1);
+    assert_eq!(union[[50, 50, 50]], (None, Some(5000)));
+    assert_eq!(union[[2, 4, -2]], (Some(2), Some(200)));
+    assert_eq!(union[[-1, -2, 1]], (Some(-1), None));
+}
+
+#[test]
+fn matrix_stencils() {
+    let mut matrix = GraphMat::with_block_size(3);
+    for x in -4..5 {
+        for y in -4..5 {
+            for z in 0..3 {
+                if (x * x + y * y + z) % 3 != 0 {
+                    matrix.set([x, y, z], (x * 7 + y * 3 - z) as f32);
+                }
+            }
+        }
+    }
+
+    // Same as summing over the kernel with get() for every offset
+    let kernel = Kernel3D::from_fn(1, |[x, y, z]| (x + 2 * y + 3 * z + 5) as f64);
+    let convolved = matrix.convolve(&kernel, Boundary::Zero);
+    assert_eq!(convolved.len(), matrix.len());
+    for (coord, value) in &convolved {
+        let mut expected = 0.0;
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let at = [coord[0] + x, coord[1] + y, coord[2] + z];
+                    expected += kernel.weight([x, y, z]) * matrix.get(at).map_or(0.0, |v| *v as f64);
+                }
+            }
+        }
+        assert_eq!(*value, expected, "at {:?}", coord);
+    }
+
+    // A constant field stays the same when blurred, with either boundary that doesn't count empty cells as 0
+    let constant = matrix.map(|_| 2.0f32);
+    let blur = Kernel3D::box_blur(2);
+    assert!(constant.convolve(&blur, Boundary::Clamp).values().all(|v| (v - 2.0).abs() < 1e-9));
+    assert!(constant.convolve(&blur, Boundary::Renormalize).values().all(|v| (v - 2.0).abs() < 1e-9));
+    assert!(constant.convolve(&blur, Boundary::Zero).values().any(|v| *v < 1.9));
+
+    let mut line = GraphMat::new();
+    for x in 0..5 {
+        line.set([x, 0, 0], (x * x) as f64);
+    }
+    let gradient = line.convolve(&Kernel3D::gradient(Axis::X), Boundary::Clamp);
+    assert_eq!(gradient[[2, 0, 0]], 4.0);
+    let laplacian = line.convolve(&Kernel3D::laplacian(), Boundary::Clamp);
+    assert_eq!(laplacian[[2, 0, 0]], 2.0);
+
+    // A general stencil, here counting the face neighbours, only kept for the cells having some
+    let counts = matrix.apply_stencil(1, |view| {
+        assert_eq!(view.get([0, 0, 0]), Some(view.center()));
+        assert_eq!(view.get([2, 0, 0]), None);
+        let faces = view.iter().filter(|(offset, _)| offset.iter().map(|c| c.abs()).sum::<i32>() == 1).count();
+        (faces > 0).then_some(faces)
+    });
+    for (coord, count) in &counts {
+        assert_eq!(*count, matrix.neighbours(coord, Connectivity::Six).count());
+    }
+    assert_eq!(counts.len(), matrix.keys().filter(|coord| matrix.neighbours(*coord, Connectivity::Six).count() > 0).count());
+}
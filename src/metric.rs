@@ -0,0 +1,69 @@
+use crate::coord::Coord;
+
+// Ways of measuring the distance between two cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Metric {
+    Euclidean,
+    // Sum of the differences along each axis
+    Manhattan,
+    // Largest of the differences along any axis
+    Chebyshev,
+}
+
+impl Metric {
+    /**
+     * @returns Length of the vector whose (absolute) components along each axis are `diff`
+     */
+    pub(crate) fn length<const D: usize>(&self, diff: [f64; D]) -> f64 {
+        match self {
+            Metric::Euclidean => diff.iter().map(|d| d * d).sum::<f64>().sqrt(),
+            Metric::Manhattan => diff.iter().sum(),
+            Metric::Chebyshev => diff.iter().fold(0.0, |max, d| d.max(max)),
+        }
+    }
+}
+
+/**
+ * @returns Absolute difference between `a` and `b` along each axis
+ *
+ * Computed in f64, since for C = i64 the difference may not fit an i64
+ */
+pub(crate) fn abs_diff<C: Coord, const D: usize>(a: [C; D], b: [C; D]) -> [f64; D] {
+    std::array::from_fn(|axis| (a[axis].to_i64() as i128 - b[axis].to_i64() as i128).unsigned_abs() as f64)
+}
+
+/**
+ * @returns The cell of the box from `min` to `max` (both inclusive) closest to `point`, along each axis independently
+ */
+pub(crate) fn closest_in_box<C: Coord, const D: usize>(point: [C; D], min: [C; D], max: [C; D]) -> [C; D] {
+    std::array::from_fn(|axis| point[axis].clamp(min[axis], max[axis]))
+}
+
+/**
+ * @brief All the cells within `radius` of `center`, as measured by `metric`
+ */
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Ball<C, const D: usize> {
+    pub(crate) center: [C; D],
+    pub(crate) radius: f64,
+    pub(crate) metric: Metric,
+}
+
+impl<C: Coord, const D: usize> Ball<C, D> {
+    pub(crate) fn contains(&self, coord: [C; D]) -> bool {
+        let diff = abs_diff(coord, self.center);
+
+        match self.metric {
+            // Comparing squares, so that points exactly on the sphere aren't missed due to rounding in sqrt()
+            Metric::Euclidean => diff.iter().map(|d| d * d).sum::<f64>() <= self.radius * self.radius,
+            _ => self.metric.length(diff) <= self.radius,
+        }
+    }
+
+    /**
+     * @returns Whether any cell in the box from `min` to `max` (both inclusive) is inside the ball
+     */
+    pub(crate) fn overlaps(&self, min: [C; D], max: [C; D]) -> bool {
+        self.contains(closest_in_box(self.center, min, max))
+    }
+}
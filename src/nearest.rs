@@ -0,0 +1,213 @@
+use generational_arena::Index as IndexInArena;
+
+use crate::coord::Coord;
+use crate::graphmat::GraphMat;
+use crate::iterators::RegionWalk;
+use crate::metric::{abs_diff, closest_in_box, Metric};
+
+// The `k` closest cells found so far, sorted by distance
+struct Closest<C, const D: usize> {
+    k: usize,
+    found: Vec<([C; D], f64)>,
+}
+
+impl<C: Coord, const D: usize> Closest<C, D> {
+    // Whether a cell at `distance` would be among the closest
+    fn accepts(&self, distance: f64) -> bool {
+        self.found.len() < self.k || distance < self.found[self.found.len() - 1].1
+    }
+
+    fn insert(&mut self, coord: [C; D], distance: f64) {
+        if self.accepts(distance) {
+            let pos = self.found.partition_point(|(_, d)| *d <= distance);
+            self.found.insert(pos, (coord, distance));
+            self.found.truncate(self.k);
+        }
+    }
+}
+
+/**
+ * @returns Offsets (in blocks) of all the blocks at a Chebyshev distance of exactly `shell` blocks from the origin
+ */
+fn shell_offsets<const D: usize>(shell: i64) -> Vec<[i64; D]> {
+    // Every offset is counted once, by the first axis along which it is at -shell or +shell
+    fn fill<const D: usize>(shell: i64, first: usize, axis: usize, offset: &mut [i64; D], out: &mut Vec<[i64; D]>) {
+        if axis == D {
+            out.push(*offset);
+            return;
+        }
+
+        let values: Vec<i64> = match axis.cmp(&first) {
+            std::cmp::Ordering::Less => (-shell + 1..shell).collect(),
+            std::cmp::Ordering::Equal => vec![-shell, shell],
+            std::cmp::Ordering::Greater => (-shell..=shell).collect(),
+        };
+        for value in values {
+            offset[axis] = value;
+            fill(shell, first, axis + 1, offset, out);
+        }
+    }
+
+    if shell == 0 {
+        return vec![[0; D]];
+    }
+
+    let mut out = Vec::new();
+    for first in 0..D {
+        fill(shell, first, 0, &mut [0; D], &mut out);
+    }
+
+    out
+}
+
+// Number of blocks in a shell, saturating
+fn shell_size(shell: i64, dimensions: u32) -> u64 {
+    if shell == 0 {
+        return 1;
+    }
+
+    match (2 * shell as u64 + 1).checked_pow(dimensions) {
+        Some(outer) => outer - (2 * shell as u64 - 1).pow(dimensions),
+        None => u64::MAX,
+    }
+}
+
+impl<T, C: Coord, const D: usize> GraphMat<T, C, D> {
+    /**
+     * @returns The cell having some data closest to `coord`, alongwith its (Euclidean) distance, None if the matrix is empty
+     *
+     * If many cells are equally close, any one of them is returned
+     */
+    pub fn nearest(&self, coord: [C; D]) -> Option<([C; D], f64)> {
+        self.nearest_where(coord, |_| true)
+    }
+
+    /**
+     * @brief Same as nearest(), but only considers the cells whose data satisfies `pred`
+     */
+    pub fn nearest_where<UnaryPredicate>(&self, coord: [C; D], pred: UnaryPredicate) -> Option<([C; D], f64)>
+    where
+        UnaryPredicate: Fn(&T) -> bool,
+    {
+        self.closest(coord, 1, pred).pop()
+    }
+
+    /**
+     * @returns Upto `k` cells having some data closest to `coord`, closest first, alongwith their (Euclidean) distances
+     */
+    pub fn k_nearest(&self, coord: [C; D], k: usize) -> Vec<([C; D], f64)> {
+        self.closest(coord, k, |_| true)
+    }
+
+    /**
+     * @brief Searches the blocks in shells of increasing distance around the block of `coord`
+     *
+     * Stops as soon as no cell in the next shell can be closer than the ones already found, so usually only a few
+     * leaders near `coord` are looked up
+     */
+    fn closest<UnaryPredicate>(&self, coord: [C; D], k: usize, pred: UnaryPredicate) -> Vec<([C; D], f64)>
+    where
+        UnaryPredicate: Fn(&T) -> bool,
+    {
+        let mut closest = Closest { k, found: Vec::new() };
+        if k == 0 {
+            return closest.found;
+        }
+
+        let block_size = self.block_size() as i64;
+        let origin = self.leader_coord(coord);
+        let mut leaders_seen = 0;
+
+        for shell in 0.. {
+            // A cell in the n-th shell is atleast (n - 1) blocks and a cell away along some axis
+            let lower_bound = if shell == 0 { 0.0 } else { ((shell - 1) * block_size + 1) as f64 };
+            if !closest.accepts(lower_bound) || leaders_seen == self.map.len() {
+                break;
+            }
+
+            let remaining = self.map.len() - leaders_seen;
+            if shell_size(shell, D as u32) > remaining as u64 {
+                // Far away leaders are sparse, going through all of them is cheaper than looking up every position of the shells
+                let mut leaders: Vec<_> = self
+                    .map
+                    .iter()
+                    // Only the ones in this shell or further, the nearer ones were seen already
+                    .filter(|(leader, _)| self.blocks_apart(origin, **leader) >= shell as u128)
+                    .map(|(leader, idx)| (self.distance_to_block(coord, *leader), *leader, *idx))
+                    .collect();
+                leaders.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                for (distance, leader, idx) in leaders {
+                    if !closest.accepts(distance) {
+                        break;
+                    }
+                    self.closest_in_block(coord, leader, idx, &pred, &mut closest);
+                }
+                break;
+            }
+
+            for offset in shell_offsets::<D>(shell) {
+                let leader = std::array::from_fn(|axis| {
+                    let c = origin[axis].to_i64() as i128 + offset[axis] as i128 * block_size as i128;
+                    i64::try_from(c).ok().and_then(C::from_i64)
+                });
+                if leader.iter().any(Option::is_none) {
+                    continue;
+                }
+                // SAFETY: Checked just above that every axis is in range of C
+                let leader = leader.map(Option::unwrap);
+
+                if let Some(idx) = self.map.get(&leader) {
+                    leaders_seen += 1;
+                    if closest.accepts(self.distance_to_block(coord, leader)) {
+                        self.closest_in_block(coord, leader, *idx, &pred, &mut closest);
+                    }
+                }
+            }
+        }
+
+        closest.found
+    }
+
+    // Chebyshev distance between two leaders, counted in blocks
+    fn blocks_apart(&self, a: [C; D], b: [C; D]) -> u128 {
+        (0..D)
+            .map(|axis| (a[axis].to_i64() as i128 - b[axis].to_i64() as i128).unsigned_abs() / self.block_size() as u128)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Distance from `coord` to the closest cell of the block of `leader_coord`
+    fn distance_to_block(&self, coord: [C; D], leader_coord: [C; D]) -> f64 {
+        let closest = closest_in_box(coord, leader_coord, self.block_end(leader_coord));
+        Metric::Euclidean.length(abs_diff(coord, closest))
+    }
+
+    // Offers every cell in the block of `leader` satisfying `pred` to `closest`
+    fn closest_in_block<UnaryPredicate>(
+        &self,
+        coord: [C; D],
+        leader_coord: [C; D],
+        leader: IndexInArena,
+        pred: &UnaryPredicate,
+        closest: &mut Closest<C, D>,
+    ) where
+        UnaryPredicate: Fn(&T) -> bool,
+    {
+        let mut walk = RegionWalk {
+            min: leader_coord,
+            max: self.block_end(leader_coord),
+            ball: None,
+            leaders: vec![leader].into_iter(),
+            stack: Vec::new(),
+        };
+
+        while let Some(idx) = walk.next_index(&self.arena) {
+            // SAFETY: next_index() only returns nodes having some data
+            let node = self.arena.get(idx).unwrap();
+            if pred(node.get().unwrap()) {
+                closest.insert(node.coord, Metric::Euclidean.length(abs_diff(coord, node.coord)));
+            }
+        }
+    }
+}
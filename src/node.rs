@@ -1,40 +1,67 @@
 use generational_arena::Index as IndexInArena;
 
+use crate::coord::Coord;
+
+// Axes of a 3D matrix, ie. the index into the coordinate, and into `Node::links` for the link going +1 along that axis
+pub(crate) const EAST: usize = 0;
+pub(crate) const NORTH: usize = 1;
+pub(crate) const SKY: usize = 2;
+
+/**
+ * @returns `coord` moved by +1 along `axis`, ie. coordinate of the neighbour `Node::links[axis]` points to
+ *
+ * None if that overflows `C`
+ */
+pub(crate) fn step_along<C: Coord, const D: usize>(coord: [C; D], axis: usize) -> Option<[C; D]> {
+    let mut next = coord;
+    next[axis] = coord[axis].offset(1)?;
+
+    Some(next)
+}
+
 // Node is an internal type, caller should not directly read it
-#[derive(Debug)]
-pub(crate) struct Node<T> {
+#[derive(Clone, Debug)]
+pub(crate) struct Node<T, C, const D: usize> {
     data: Option<T>,    // when this is just a node to get to some other node, then it is None
-    pub coord: (i32,i32,i32),
+    pub coord: [C; D],
 
     // Make it Mutex, for multi-thread use
     // Way 2: Message passing, node sends index, GraphMat replies with node, but that is slow since blocks
-    pub(crate) north: Option<IndexInArena>,
-    pub(crate) east: Option<IndexInArena>,
-    pub(crate) sky: Option<IndexInArena>,
+    //
+    // links[axis] is the neighbour at +1 along that axis, for eg. in 3D these are the east, north and sky neighbours
+    pub(crate) links: [Option<IndexInArena>; D],
 }
 
-impl<T> Node<T> {
-    pub fn new(data: Option<T>, coord: (i32,i32,i32)) -> Self {
+impl<T, C, const D: usize> Node<T, C, D> {
+    pub fn new(data: Option<T>, coord: [C; D]) -> Self {
         Node {
             data,
             coord,
 
-            north: None,
-            east: None,
-            sky: None,
+            links: [None; D],
         }
     }
 
-    pub fn get<'a>(&'a self) -> Option<&'a T> {
+    pub fn get(&self) -> Option<&T> {
         self.data.as_ref()
     }
 
-    pub fn get_mut<'a>(&'a mut self) -> Option<&'a mut T> {
+    pub fn get_mut(&mut self) -> Option<&mut T> {
         self.data.as_mut()
     }
 
-    pub fn set(&mut self, data: T) {
-        self.data.replace(data);
+    /**
+     * @returns The data previously stored in this node, if any
+     */
+    pub fn set(&mut self, data: T) -> Option<T> {
+        self.data.replace(data)
+    }
+
+    pub fn take(&mut self) -> Option<T> {
+        self.data.take()
     }
 
+    pub fn has_links(&self) -> bool {
+        self.links.iter().any(Option::is_some)
+    }
 }
@@ -0,0 +1,218 @@
+// Shortest paths between two cells, through neighbouring cells
+//
+// For eg. matrix.shortest_path(start, goal, Connectivity::Six, |cell| cell.walkable, |cell| cell.cost, PathOptions::default())
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::coord::Coord;
+use crate::direction::Connectivity;
+use crate::entry::Entry;
+use crate::graphmat::GraphMat;
+use crate::metric::{abs_diff, Metric};
+
+pub enum Algorithm<'h, C = i32> {
+    // Fewest steps, ignoring the costs
+    Bfs,
+    // Least total cost
+    Dijkstra,
+    // Least total cost, exploring the cells closer to the goal first as estimated by the heuristic,
+    // for eg. Algorithm::AStar(&pathfinding::euclidean)
+    // The path is the shortest only if the heuristic never overestimates the remaining cost
+    AStar(&'h dyn Fn([C; 3], [C; 3]) -> f64),
+}
+
+pub struct PathOptions<'h, C = i32> {
+    pub algorithm: Algorithm<'h, C>,
+    // If true, the path goes through the empty cells, and only the cells having some data that are also `passable`
+    // Empty cells cost 1, and the search stays within a cell of the box containing the cells having some data, start and goal
+    pub through_empty: bool,
+}
+
+impl<'h, C> Default for PathOptions<'h, C> {
+    fn default() -> Self {
+        PathOptions {
+            algorithm: Algorithm::Dijkstra,
+            through_empty: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path<C = i32> {
+    // From start to goal, both inclusive
+    pub cells: Vec<[C; 3]>,
+    pub cost: f64,
+}
+
+/**
+ * @brief Straight line distance, a heuristic for Algorithm::AStar that never overestimates if every cell costs atleast 1
+ */
+pub fn euclidean<C: Coord>(a: [C; 3], b: [C; 3]) -> f64 {
+    Metric::Euclidean.length(abs_diff(a, b))
+}
+
+/**
+ * @brief Sum of the differences along each axis, never overestimates with Connectivity::Six if every cell costs atleast 1
+ */
+pub fn manhattan<C: Coord>(a: [C; 3], b: [C; 3]) -> f64 {
+    Metric::Manhattan.length(abs_diff(a, b))
+}
+
+// A cell waiting in the BinaryHeap, the one with the least `priority` is popped first
+struct Queued<C> {
+    priority: f64,
+    coord: [C; 3],
+}
+
+impl<C> PartialEq for Queued<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C> Eq for Queued<C> {}
+
+impl<C> PartialOrd for Queued<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C> Ord for Queued<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, since BinaryHeap pops the greatest
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+// Only for 3D matrices, since neighbours are found through `Direction`
+impl<T, C: Coord> GraphMat<T, C> {
+    /**
+     * @returns The shortest path from `start` to `goal` through the cells whose data is `passable`, None if there is none
+     *
+     * Moving into a cell costs cost(data) times the length of the step, ie. 1 to a face neighbour, and √2 or √3 diagonally
+     * Costs must not be negative. The cost returned is of the path found, even with Algorithm::Bfs
+     */
+    pub fn shortest_path<Passable, Cost>(
+        &self,
+        start: [C; 3],
+        goal: [C; 3],
+        connectivity: Connectivity,
+        passable: Passable,
+        cost: Cost,
+        options: PathOptions<'_, C>,
+    ) -> Option<Path<C>>
+    where
+        Passable: Fn(&T) -> bool,
+        Cost: Fn(&T) -> f64,
+    {
+        // Box the search through empty cells is limited to, so that it ends even if the goal can't be reached
+        let limits = options.through_empty.then(|| {
+            let (mut min, mut max) = self.bounds().unwrap_or((start, start));
+            for coord in [start, goal] {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(coord[axis]);
+                    max[axis] = max[axis].max(coord[axis]);
+                }
+            }
+            (min.map(|c| c.offset(-1).unwrap_or(c)), max.map(|c| c.offset(1).unwrap_or(c)))
+        });
+
+        // Cells that can be moved into from `coord`, alongwith the cost of that move
+        let moves = |coord: [C; 3]| -> Vec<([C; 3], f64)> {
+            let step_cost = |next: [C; 3], cell_cost: f64| cell_cost * euclidean(coord, next);
+
+            match limits {
+                None => self
+                    .neighbours(coord, connectivity)
+                    .filter(|(_, _, data)| passable(data))
+                    .map(|(_, next, data)| (next, step_cost(next, cost(data))))
+                    .collect(),
+                Some((min, max)) => connectivity
+                    .directions()
+                    .filter_map(|dir| {
+                        let offset = dir.offset();
+                        let next = [0, 1, 2].map(|axis| coord[axis].offset(offset[axis] as i64));
+                        let next = [next[0]?, next[1]?, next[2]?];
+                        if !(0..3).all(|axis| (min[axis]..=max[axis]).contains(&next[axis])) {
+                            return None;
+                        }
+
+                        match self.get(next) {
+                            None => Some((next, step_cost(next, 1.0))),
+                            Some(data) if passable(data) => Some((next, step_cost(next, cost(data)))),
+                            Some(_) => None,
+                        }
+                    })
+                    .collect(),
+            }
+        };
+
+        // Cells reached so far, alongwith the cell they were reached from and the cost to reach them
+        let mut reached: GraphMat<([C; 3], f64), C> = GraphMat::with_block_size(self.block_size());
+        reached.set(start, (start, 0.0));
+
+        match options.algorithm {
+            Algorithm::Bfs => {
+                let mut queue = VecDeque::from([start]);
+                while let Some(coord) = queue.pop_front() {
+                    if coord == goal {
+                        break;
+                    }
+
+                    let cost_here = reached[coord].1;
+                    for (next, step_cost) in moves(coord) {
+                        if let Entry::Vacant(entry) = reached.entry(next) {
+                            entry.insert((coord, cost_here + step_cost));
+                            queue.push_back(next);
+                        }
+                    }
+                }
+            }
+            Algorithm::Dijkstra | Algorithm::AStar(_) => {
+                let heuristic = |coord| match options.algorithm {
+                    Algorithm::AStar(heuristic) => heuristic(coord, goal),
+                    _ => 0.0,
+                };
+
+                let mut heap = BinaryHeap::from([Queued { priority: heuristic(start), coord: start }]);
+                while let Some(Queued { priority, coord }) = heap.pop() {
+                    if coord == goal {
+                        break;
+                    }
+
+                    let cost_here = reached[coord].1;
+                    // Skipping the stale entries, pushed before a cheaper way to this cell was found
+                    if priority > cost_here + heuristic(coord) {
+                        continue;
+                    }
+
+                    for (next, step_cost) in moves(coord) {
+                        let cost_next = cost_here + step_cost;
+                        match reached.entry(next) {
+                            Entry::Occupied(mut entry) if cost_next < entry.get().1 => {
+                                entry.insert((coord, cost_next));
+                            }
+                            Entry::Vacant(entry) => {
+                                entry.insert((coord, cost_next));
+                            }
+                            Entry::Occupied(_) => continue,
+                        }
+                        heap.push(Queued { priority: cost_next + heuristic(next), coord: next });
+                    }
+                }
+            }
+        }
+
+        // Following the cells back from the goal
+        let &(_, total_cost) = reached.get(goal)?;
+        let mut cells = vec![goal];
+        while cells[cells.len() - 1] != start {
+            cells.push(reached[cells[cells.len() - 1]].0);
+        }
+        cells.reverse();
+
+        Some(Path { cells, cost: total_cost })
+    }
+}
@@ -0,0 +1,309 @@
+use std::iter::FusedIterator;
+
+use crate::coord::Coord;
+use crate::direction::Direction;
+use crate::graphmat::GraphMat;
+
+/**
+ * @brief A cell having some data, reached by GraphMat::raycast() or GraphMat::line()
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit<'a, T, C = i32> {
+    pub coord: [C; 3],
+    // Direction from the cell towards the one the ray came from, ie. the face through which the ray entered (this can
+    // also be an edge or a corner for line()), None for the cell containing the origin
+    pub face: Option<Direction>,
+    // Distance travelled from the origin, upto where the ray entered the cell (line() gives the distance to the cell itself)
+    pub distance: f64,
+    pub data: &'a T,
+}
+
+// The hit at `cell`, if it is in range of C and has some data
+fn hit_at<'a, T, C: Coord>(
+    graphmat: &'a GraphMat<T, C>,
+    cell: [i64; 3],
+    face: Option<Direction>,
+    distance: f64,
+) -> Option<Hit<'a, T, C>> {
+    let coord = [C::from_i64(cell[0])?, C::from_i64(cell[1])?, C::from_i64(cell[2])?];
+
+    graphmat.get(coord).map(|data| Hit {
+        coord,
+        face,
+        distance,
+        data,
+    })
+}
+
+/**
+ * @brief Iterator over the cells having some data that a ray passes through, see GraphMat::raycast()
+ *
+ * Uses the voxel traversal of Amanatides & Woo, moving one cell at a time to whichever neighbour the ray enters first
+ */
+pub struct Raycast<'a, T, C = i32> {
+    pub(crate) graphmat: &'a GraphMat<T, C>,
+    // None once the ray has travelled max_dist, or left the bounds() of the matrix
+    pub(crate) cell: Option<[i64; 3]>,
+    pub(crate) face: Option<Direction>,
+    pub(crate) distance: f64,
+    pub(crate) max_dist: f64,
+    // -1 or +1 along each axis, depending on the sign of the direction (0 if the ray doesn't move along it)
+    pub(crate) step: [i64; 3],
+    // Distance along the ray at which it crosses the next cell boundary along each axis
+    pub(crate) t_max: [f64; 3],
+    // Distance along the ray between two cell boundaries along each axis
+    pub(crate) t_delta: [f64; 3],
+}
+
+impl<'a, T, C: Coord> Raycast<'a, T, C> {
+    // Moves to the next cell the ray passes through
+    fn advance(&mut self) {
+        let Some(mut cell) = self.cell else {
+            return;
+        };
+
+        // SAFETY: There are 3 axes, so there is a minimum
+        let axis = (0..3).min_by(|a, b| self.t_max[*a].total_cmp(&self.t_max[*b])).unwrap();
+        if self.t_max[axis] > self.max_dist {
+            self.cell = None;
+            return;
+        }
+
+        cell[axis] = match cell[axis].checked_add(self.step[axis]) {
+            Some(c) => c,
+            None => {
+                self.cell = None;
+                return;
+            }
+        };
+        let mut entered_from = [0; 3];
+        entered_from[axis] = -self.step[axis] as i32;
+
+        self.cell = Some(cell);
+        self.face = Direction::from_offset(entered_from);
+        self.distance = self.t_max[axis];
+        self.t_max[axis] += self.t_delta[axis];
+    }
+}
+
+impl<'a, T, C: Coord> Iterator for Raycast<'a, T, C> {
+    type Item = Hit<'a, T, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Cells out of range of C have no data, hit_at() skips them
+            let cell = self.cell?;
+            let hit = hit_at(self.graphmat, cell, self.face, self.distance);
+            self.advance();
+
+            if hit.is_some() {
+                return hit;
+            }
+        }
+    }
+}
+
+impl<'a, T, C: Coord> FusedIterator for Raycast<'a, T, C> {}
+
+/**
+ * @brief Iterator over the cells having some data on the line between two cells, see GraphMat::line()
+ *
+ * Uses 3D Bresenham, so exactly one cell is visited for each step along the axis with the largest difference
+ */
+pub struct Line<'a, T, C = i32> {
+    pub(crate) graphmat: &'a GraphMat<T, C>,
+    pub(crate) start: [i64; 3],
+    // None once `end` has been visited
+    pub(crate) cell: Option<[i64; 3]>,
+    pub(crate) face: Option<Direction>,
+    pub(crate) end: [i64; 3],
+    pub(crate) step: [i64; 3],
+    pub(crate) abs_diff: [i128; 3],
+    // Axis with the largest difference, which moves on every step
+    pub(crate) main_axis: usize,
+    // Decision variables of the other two axes, the axis moves when this becomes non-negative
+    pub(crate) error: [i128; 3],
+}
+
+impl<'a, T, C: Coord> Line<'a, T, C> {
+    fn advance(&mut self) {
+        let Some(mut cell) = self.cell else {
+            return;
+        };
+        if cell == self.end {
+            self.cell = None;
+            return;
+        }
+
+        let mut entered_from = [0; 3];
+        let main = self.main_axis;
+        for axis in (0..3).filter(|axis| *axis != main) {
+            if self.error[axis] >= 0 {
+                cell[axis] += self.step[axis];
+                entered_from[axis] = -self.step[axis] as i32;
+                self.error[axis] -= 2 * self.abs_diff[main];
+            }
+            self.error[axis] += 2 * self.abs_diff[axis];
+        }
+        cell[main] += self.step[main];
+        entered_from[main] = -self.step[main] as i32;
+
+        self.cell = Some(cell);
+        self.face = Direction::from_offset(entered_from);
+    }
+}
+
+impl<'a, T, C: Coord> Iterator for Line<'a, T, C> {
+    type Item = Hit<'a, T, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let cell = self.cell?;
+
+            let distance = (0..3)
+                .map(|axis| ((cell[axis] as i128 - self.start[axis] as i128) as f64).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            let hit = hit_at(self.graphmat, cell, self.face, distance);
+            self.advance();
+
+            if hit.is_some() {
+                return hit;
+            }
+        }
+    }
+}
+
+impl<'a, T, C: Coord> FusedIterator for Line<'a, T, C> {}
+
+// Distances along the ray at which it enters and leaves the box from `min` to `max`, None if it never passes through the box
+// The distance of entry is 0 if `origin` is already inside the box
+fn box_crossing(origin: [f64; 3], dir: [f64; 3], min: [f64; 3], max: [f64; 3]) -> Option<(f64, f64)> {
+    let (mut enter, mut exit) = (0.0_f64, f64::INFINITY);
+    for axis in 0..3 {
+        if dir[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] >= max[axis] {
+                return None;
+            }
+        } else {
+            let (t0, t1) = ((min[axis] - origin[axis]) / dir[axis], (max[axis] - origin[axis]) / dir[axis]);
+            enter = enter.max(t0.min(t1));
+            exit = exit.min(t0.max(t1));
+        }
+    }
+
+    (enter <= exit).then_some((enter, exit))
+}
+
+// Only for 3D matrices, since the hits are reported with a `Direction`
+impl<T, C: Coord> GraphMat<T, C> {
+    /**
+     * @brief Iterates over the cells having some data that a ray passes through, nearest first
+     *
+     * The cell [x, y, z] is taken to be the unit cube from (x, y, z) to (x + 1, y + 1, z + 1), and the ray starts from
+     * `origin` going along `dir` (need not be normalised) upto a distance of `max_dist`
+     * Nothing is visited if `dir` is zero, or any of the values is not finite (except `max_dist`, which can be infinite, since
+     * the ray stops once it leaves the bounds() of the matrix anyway)
+     * The origin can be outside the bounds, or even the range of C, the ray is only followed from where it enters the bounds
+     */
+    pub fn raycast(&self, origin: [f64; 3], dir: [f64; 3], max_dist: f64) -> Raycast<'_, T, C> {
+        let length = dir.iter().map(|d| d * d).sum::<f64>().sqrt();
+        let valid = length > 0.0 && length.is_finite() && origin.iter().all(|o| o.is_finite()) && !max_dist.is_nan();
+
+        let dir = dir.map(|d| d / length);
+
+        // No cell outside the box around the occupied cells has data, so the ray starts where it enters the box, and stops
+        // where it leaves it, even for an infinite max_dist
+        let crossing = match (valid, self.bounds()) {
+            (true, Some((min, max))) => {
+                let (min, max) = (min.map(Coord::to_i64), max.map(Coord::to_i64));
+                let (min_f, max_f) = (min.map(|c| c as f64), max.map(|c| c as f64 + 1.0));
+                box_crossing(origin, dir, min_f, max_f).map(|(enter, exit)| (min, max, min_f, max_f, enter, exit))
+            }
+            _ => None,
+        };
+
+        let mut cell = origin.map(|o| o.floor() as i64);
+        let (mut face, mut distance) = (None, 0.0);
+        let max_dist = match crossing {
+            Some((min, max, min_f, max_f, enter, exit)) if enter <= max_dist => {
+                if enter > 0.0 {
+                    // Clamped, so that rounding can't put the first cell just outside the box
+                    for axis in 0..3 {
+                        cell[axis] = ((origin[axis] + dir[axis] * enter).floor() as i64).clamp(min[axis], max[axis]);
+                    }
+
+                    // The ray enters through the side of the box it reaches last
+                    let near = |axis: usize| (if dir[axis] > 0.0 { min_f[axis] } else { max_f[axis] } - origin[axis]) / dir[axis];
+                    // SAFETY: `dir` is not zero, so it moves along at least one axis
+                    let axis = (0..3).filter(|axis| dir[*axis] != 0.0).max_by(|a, b| near(*a).total_cmp(&near(*b))).unwrap();
+                    let mut entered_from = [0; 3];
+                    entered_from[axis] = if dir[axis] > 0.0 { -1 } else { 1 };
+
+                    face = Direction::from_offset(entered_from);
+                    distance = enter;
+                }
+                max_dist.min(exit)
+            }
+            _ => -1.0,
+        };
+
+        let mut step = [0; 3];
+        let mut t_max = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            if dir[axis] > 0.0 {
+                step[axis] = 1;
+                t_max[axis] = (cell[axis] as f64 + 1.0 - origin[axis]) / dir[axis];
+                t_delta[axis] = 1.0 / dir[axis];
+            } else if dir[axis] < 0.0 {
+                step[axis] = -1;
+                t_max[axis] = (origin[axis] - cell[axis] as f64) / -dir[axis];
+                t_delta[axis] = 1.0 / -dir[axis];
+            }
+        }
+
+        Raycast {
+            graphmat: self,
+            cell: if max_dist >= 0.0 { Some(cell) } else { None },
+            face,
+            distance,
+            max_dist,
+            step,
+            t_max,
+            t_delta,
+        }
+    }
+
+    /**
+     * @brief Iterates over the cells having some data on the line from `a` to `b` (both inclusive), starting from `a`
+     */
+    pub fn line(&self, a: [C; 3], b: [C; 3]) -> Line<'_, T, C> {
+        let start = a.map(Coord::to_i64);
+        let end = b.map(Coord::to_i64);
+
+        let step = [0, 1, 2].map(|axis| (end[axis] as i128 - start[axis] as i128).signum() as i64);
+        let abs_diff = [0, 1, 2].map(|axis| (end[axis] as i128 - start[axis] as i128).abs());
+        // SAFETY: There are 3 axes, so there is a maximum
+        let main_axis = (0..3).max_by_key(|axis| abs_diff[*axis]).unwrap();
+
+        Line {
+            graphmat: self,
+            start,
+            cell: Some(start),
+            face: None,
+            end,
+            step,
+            abs_diff,
+            main_axis,
+            error: [0, 1, 2].map(|axis| 2 * abs_diff[axis] - abs_diff[main_axis]),
+        }
+    }
+
+    /**
+     * @returns The first cell having some data that the ray passes through, see raycast()
+     */
+    pub fn first_hit(&self, origin: [f64; 3], dir: [f64; 3], max_dist: f64) -> Option<Hit<'_, T, C>> {
+        self.raycast(origin, dir, max_dist).next()
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::ops::Add;
+
+use crate::coord::Coord;
+use crate::direction::{Axis, Side};
+use crate::graphmat::{GraphMat, GraphMat2D};
+
+// Only for 3D matrices, since `Axis` is 3D
+impl<T, C: Coord> GraphMat<T, C> {
+    /**
+     * @brief Collapses the matrix along `axis`, folding the cells of each column (starting with `init`) into one value
+     *
+     * @returns 2D matrix indexed by the other two axes in order (as in slice()), having a value only for the columns
+     * having some data. The cells of a column are folded in order of their coordinate along `axis`, smallest first
+     */
+    pub fn reduce_axis<U, F>(&self, axis: Axis, init: U, fold: F) -> GraphMat2D<U, C>
+    where
+        U: Clone,
+        F: Fn(U, &T) -> U,
+    {
+        self.fold_columns(axis, |_, data| fold(init.clone(), data), |acc, _, data| fold(acc, data))
+    }
+
+    pub fn sum_axis(&self, axis: Axis) -> GraphMat2D<T, C>
+    where
+        T: Copy + Default + Add<Output = T>,
+    {
+        self.reduce_axis(axis, T::default(), |sum, data| sum + *data)
+    }
+
+    pub fn max_axis(&self, axis: Axis) -> GraphMat2D<T, C>
+    where
+        T: Copy + PartialOrd,
+    {
+        self.fold_columns(axis, |_, data| *data, |max, _, data| if *data > max { *data } else { max })
+    }
+
+    pub fn min_axis(&self, axis: Axis) -> GraphMat2D<T, C>
+    where
+        T: Copy + PartialOrd,
+    {
+        self.fold_columns(axis, |_, data| *data, |min, _, data| if *data < min { *data } else { min })
+    }
+
+    /**
+     * @returns Number of cells having some data in each column
+     */
+    pub fn count_axis(&self, axis: Axis) -> GraphMat2D<usize, C> {
+        self.reduce_axis(axis, 0, |count, _| count + 1)
+    }
+
+    /**
+     * @brief The first cell having some data in each column, coming from the `from` end of `axis`
+     *
+     * For eg. first_along(Axis::Z, Side::Max) is the topmost cell of each column, like a height map
+     *
+     * @returns The coordinate along `axis` of that cell, alongwith its data
+     */
+    pub fn first_along(&self, axis: Axis, from: Side) -> GraphMat2D<(C, &T), C> {
+        let axis_idx = axis.index();
+        let is_before = move |a: C, b: C| match from {
+            Side::Min => a < b,
+            Side::Max => a > b,
+        };
+
+        self.fold_columns(
+            axis,
+            |coord, data| (coord[axis_idx], data),
+            |first, coord, data| if is_before(coord[axis_idx], first.0) { (coord[axis_idx], data) } else { first },
+        )
+    }
+
+    // The core of all the reductions, `first` gives the value for the first cell of a column along `axis`, and `fold` adds each of the others in order
+    fn fold_columns<'a, U, First, Fold>(&'a self, axis: Axis, first: First, fold: Fold) -> GraphMat2D<U, C>
+    where
+        First: Fn([C; 3], &'a T) -> U,
+        Fold: Fn(U, [C; 3], &'a T) -> U,
+    {
+        let (axis_idx, others) = (axis.index(), axis.others());
+
+        let mut columns: HashMap<[C; 2], Vec<([C; 3], &'a T)>> = HashMap::new();
+        for (coord, data) in self.iter() {
+            columns.entry(others.map(|other| coord[other])).or_default().push((coord, data));
+        }
+
+        // Sorted along the axis, since the order of iter() depends on which slots of the arena were free at each insertion
+        let columns = columns.into_iter().map(|(column, mut cells)| {
+            cells.sort_unstable_by_key(|(coord, _)| coord[axis_idx]);
+
+            let mut cells = cells.into_iter();
+            // SAFETY: A column is only added above alongwith a cell
+            let (coord, data) = cells.next().unwrap();
+            (column, cells.fold(first(coord, data), |acc, (coord, data)| fold(acc, coord, data)))
+        });
+
+        let mut result = GraphMat::with_block_size(self.block_size());
+        result.extend(columns);
+
+        result
+    }
+}
@@ -0,0 +1,214 @@
+use std::iter::FusedIterator;
+
+use generational_arena::Index as IndexInArena;
+
+use crate::coord::Coord;
+use crate::direction::Axis;
+use crate::graphmat::GraphMat;
+use crate::iterators::{Region, RegionMut, RegionWalk};
+
+// Coordinate in the matrix of the cell at `coord` in the plane where `axis` is `value`
+fn to_3d<C: Coord>(axis: Axis, value: C, coord: [C; 2]) -> [C; 3] {
+    let [a, b] = axis.others();
+    let mut coord_3d = [value; 3];
+    coord_3d[a] = coord[0];
+    coord_3d[b] = coord[1];
+
+    coord_3d
+}
+
+fn to_2d<C: Coord>(axis: Axis, coord: [C; 3]) -> [C; 2] {
+    axis.others().map(|other| coord[other])
+}
+
+// The walk over all cells of the plane, starting only from the leaders whose block overlaps the plane within bounds()
+fn plane_walk<T, C: Coord>(graphmat: &GraphMat<T, C>, axis: Axis, value: C) -> RegionWalk<C, 3> {
+    let axis_idx = axis.index();
+    let (mut min, mut max) = graphmat.bounds().unwrap_or(([C::MAX; 3], [C::MIN; 3]));
+    min[axis_idx] = min[axis_idx].max(value);
+    max[axis_idx] = max[axis_idx].min(value);
+
+    // Nothing to visit if the matrix is empty, or the plane lies outside of its bounds
+    let leaders: Vec<IndexInArena> = if (0..3).all(|axis| min[axis] <= max[axis]) {
+        graphmat.leaders_in_box(min, max, None)
+    } else {
+        Vec::new()
+    };
+
+    RegionWalk {
+        min,
+        max,
+        ball: None,
+        leaders: leaders.into_iter(),
+        stack: Vec::new(),
+    }
+}
+
+// Smallest box containing all the cells of the plane, along the two axes of the plane
+fn plane_bounds<T, C: Coord>(graphmat: &GraphMat<T, C>, axis: Axis, value: C) -> Option<([C; 2], [C; 2])> {
+    let mut cells = SliceIter {
+        inner: Region {
+            graphmat,
+            walk: plane_walk(graphmat, axis, value),
+        },
+        axis,
+    };
+
+    let (first, _) = cells.next()?;
+    Some(cells.fold((first, first), |(mut min, mut max), (coord, _)| {
+        for i in 0..2 {
+            min[i] = min[i].min(coord[i]);
+            max[i] = max[i].max(coord[i]);
+        }
+        (min, max)
+    }))
+}
+
+/**
+ * @brief Read only view of the plane of a 3D matrix where one axis has a fixed value, see GraphMat::slice()
+ *
+ * Cells are indexed by the other two axes in order, for eg. [x, z] for a slice along Axis::Y
+ */
+pub struct Slice2D<'a, T, C = i32> {
+    pub(crate) graphmat: &'a GraphMat<T, C>,
+    pub(crate) axis: Axis,
+    pub(crate) value: C,
+}
+
+impl<'a, T, C: Coord> Slice2D<'a, T, C> {
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+
+    pub fn value(&self) -> C {
+        self.value
+    }
+
+    pub fn get(&self, coord: [C; 2]) -> Option<&'a T> {
+        self.graphmat.get(to_3d(self.axis, self.value, coord))
+    }
+
+    /**
+     * @brief Iterates over the cells of the plane having some data, in no particular order
+     */
+    pub fn iter(&self) -> SliceIter<'a, T, C> {
+        SliceIter {
+            inner: Region {
+                graphmat: self.graphmat,
+                walk: plane_walk(self.graphmat, self.axis, self.value),
+            },
+            axis: self.axis,
+        }
+    }
+
+    /**
+     * @returns Inclusive (min, max) corners of the smallest rectangle containing the cells of the plane having some data
+     */
+    pub fn bounds(&self) -> Option<([C; 2], [C; 2])> {
+        plane_bounds(self.graphmat, self.axis, self.value)
+    }
+}
+
+/**
+ * @brief Same as Slice2D, but the cells can be modified, see GraphMat::slice_mut()
+ */
+pub struct Slice2DMut<'a, T, C = i32> {
+    pub(crate) graphmat: &'a mut GraphMat<T, C>,
+    pub(crate) axis: Axis,
+    pub(crate) value: C,
+}
+
+impl<'a, T, C: Coord> Slice2DMut<'a, T, C> {
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+
+    pub fn value(&self) -> C {
+        self.value
+    }
+
+    pub fn get(&self, coord: [C; 2]) -> Option<&T> {
+        self.graphmat.get(to_3d(self.axis, self.value, coord))
+    }
+
+    pub fn get_mut(&mut self, coord: [C; 2]) -> Option<&mut T> {
+        self.graphmat.get_mut(to_3d(self.axis, self.value, coord))
+    }
+
+    pub fn set(&mut self, coord: [C; 2], data: T) {
+        self.graphmat.set(to_3d(self.axis, self.value, coord), data)
+    }
+
+    pub fn remove(&mut self, coord: [C; 2]) -> Option<T> {
+        self.graphmat.remove(to_3d(self.axis, self.value, coord))
+    }
+
+    pub fn iter(&self) -> SliceIter<'_, T, C> {
+        SliceIter {
+            inner: Region {
+                graphmat: self.graphmat,
+                walk: plane_walk(self.graphmat, self.axis, self.value),
+            },
+            axis: self.axis,
+        }
+    }
+
+    /**
+     * @brief Calls `f` with each cell of the plane having some data, in no particular order
+     */
+    pub fn for_each_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut([C; 2], &mut T),
+    {
+        let axis = self.axis;
+        let region = RegionMut {
+            walk: plane_walk(self.graphmat, self.axis, self.value),
+            graphmat: &mut *self.graphmat,
+        };
+
+        region.for_each_mut(|coord, data| f(to_2d(axis, coord), data));
+    }
+
+    pub fn bounds(&self) -> Option<([C; 2], [C; 2])> {
+        plane_bounds(self.graphmat, self.axis, self.value)
+    }
+}
+
+pub struct SliceIter<'a, T, C = i32> {
+    pub(crate) inner: Region<'a, T, C, 3>,
+    pub(crate) axis: Axis,
+}
+
+impl<'a, T, C: Coord> Iterator for SliceIter<'a, T, C> {
+    type Item = ([C; 2], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(coord, data)| (to_2d(self.axis, coord), data))
+    }
+}
+
+impl<'a, T, C: Coord> FusedIterator for SliceIter<'a, T, C> {}
+
+// Only for 3D matrices, since `Axis` is 3D
+impl<T, C: Coord> GraphMat<T, C> {
+    /**
+     * @brief View of the plane where `axis` is `value`, for eg. slice(Axis::Z, 5) is the XY plane at z = 5
+     *
+     * Only the leaders whose block contains the plane are walked
+     */
+    pub fn slice(&self, axis: Axis, value: C) -> Slice2D<'_, T, C> {
+        Slice2D {
+            graphmat: self,
+            axis,
+            value,
+        }
+    }
+
+    pub fn slice_mut(&mut self, axis: Axis, value: C) -> Slice2DMut<'_, T, C> {
+        Slice2DMut {
+            graphmat: self,
+            axis,
+            value,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use generational_arena::Index as IndexInArena;
+
+use crate::coord::Coord;
+use crate::direction::Axis;
+use crate::graphmat::{GraphMat, Walk};
+use crate::iterators::RegionWalk;
+
+/**
+ * @brief Weights of a cube of (2 * radius + 1)^3 cells around a cell, for GraphMat::convolve()
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel3D {
+    radius: usize,
+    // Indexed by [x][y][z] offset (each from -radius to radius), flattened with z varying fastest
+    weights: Vec<f64>,
+}
+
+impl Kernel3D {
+    /**
+     * @brief Panics if there aren't exactly (2 * radius + 1)^3 weights, ordered by the [x][y][z] offset, z varying fastest
+     */
+    pub fn new(radius: usize, weights: Vec<f64>) -> Self {
+        let side = 2 * radius + 1;
+        assert_eq!(weights.len(), side * side * side, "A kernel of radius {} needs {} weights", radius, side * side * side);
+
+        Kernel3D { radius, weights }
+    }
+
+    /**
+     * @brief Kernel whose weight at each [x, y, z] offset is f(offset)
+     */
+    pub fn from_fn<F>(radius: usize, f: F) -> Self
+    where
+        F: Fn([i32; 3]) -> f64,
+    {
+        Kernel3D::new(radius, cube_offsets(radius).map(f).collect())
+    }
+
+    /**
+     * @brief Average of all cells in the cube
+     */
+    pub fn box_blur(radius: usize) -> Self {
+        let side = (2 * radius + 1) as f64;
+        Kernel3D::from_fn(radius, |_| 1.0 / (side * side * side))
+    }
+
+    /**
+     * @brief Sum of the 6 face neighbours, minus 6 times the cell
+     */
+    pub fn laplacian() -> Self {
+        Kernel3D::from_fn(1, |offset| match offset.iter().map(|c| c.abs()).sum() {
+            0 => -6.0,
+            1 => 1.0,
+            _ => 0.0,
+        })
+    }
+
+    /**
+     * @brief Central difference along `axis`, ie. half of (next cell - previous cell)
+     */
+    pub fn gradient(axis: Axis) -> Self {
+        let axis = axis.index();
+        Kernel3D::from_fn(1, |offset| {
+            if (0..3).all(|other| other == axis || offset[other] == 0) {
+                offset[axis] as f64 / 2.0
+            } else {
+                0.0
+            }
+        })
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    /**
+     * @returns The weight at `offset`, 0 beyond the radius
+     */
+    pub fn weight(&self, offset: [i32; 3]) -> f64 {
+        match cube_index(self.radius, offset) {
+            Some(i) => self.weights[i],
+            None => 0.0,
+        }
+    }
+}
+
+/**
+ * @brief What GraphMat::convolve() uses for the cells around a cell that have no data
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    // Empty cells count as 0
+    Zero,
+    // Empty cells take the value of the cell at the center
+    Clamp,
+    // Empty cells are left out, and the result is scaled up by (sum of all weights / sum of the weights used),
+    // meant for kernels with only positive weights, like blurs
+    Renormalize,
+}
+
+// All the offsets within `radius` along each axis, in the order of Kernel3D::weights
+fn cube_offsets(radius: usize) -> impl Iterator<Item = [i32; 3]> {
+    let r = radius as i32;
+    (-r..=r).flat_map(move |x| (-r..=r).flat_map(move |y| (-r..=r).map(move |z| [x, y, z])))
+}
+
+// Index of `offset` in Kernel3D::weights (or NeighbourhoodView::cells), None if it is beyond `radius`
+fn cube_index(radius: usize, offset: [i32; 3]) -> Option<usize> {
+    let side = 2 * radius + 1;
+    let mut index = 0;
+    for c in offset {
+        let c = usize::try_from(c as i64 + radius as i64).ok().filter(|c| *c < side)?;
+        index = index * side + c;
+    }
+
+    Some(index)
+}
+
+/**
+ * @brief The cells around a cell, upto `radius` along each axis, given to the function of GraphMat::apply_stencil()
+ */
+pub struct NeighbourhoodView<'a, T, C = i32> {
+    coord: [C; 3],
+    radius: usize,
+    // In the order of cube_offsets(), ie. the same as Kernel3D::weights
+    cells: Vec<Option<&'a T>>,
+}
+
+impl<'a, T, C: Coord> NeighbourhoodView<'a, T, C> {
+    /**
+     * @returns Coordinate of the cell at the center
+     */
+    pub fn coord(&self) -> [C; 3] {
+        self.coord
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    pub fn center(&self) -> &'a T {
+        // SAFETY: A view is only made for cells having some data
+        self.get([0, 0, 0]).unwrap()
+    }
+
+    /**
+     * @returns Value of the cell at `offset` from the center, None if it has no data, or is beyond the radius
+     */
+    pub fn get(&self, offset: [i32; 3]) -> Option<&'a T> {
+        self.cells[cube_index(self.radius, offset)?]
+    }
+
+    /**
+     * @brief Iterates over the cells of the neighbourhood having some data (including the center), with their offsets
+     */
+    pub fn iter(&self) -> impl Iterator<Item = ([i32; 3], &'a T)> + '_ {
+        cube_offsets(self.radius)
+            .zip(self.cells.iter())
+            .filter_map(|(offset, data)| data.map(|data| (offset, data)))
+    }
+}
+
+// Only for 3D matrices, since `Kernel3D` is 3D
+impl<T, C: Coord> GraphMat<T, C> {
+    /**
+     * @brief Applies `f` to the neighbourhood of every cell having some data, the result has the values `f` returned Some for
+     *
+     * Cells are visited block by block, the leaders of the blocks around a block are looked up once for all its cells,
+     * then each cell around is reached by following links from those leaders
+     */
+    pub fn apply_stencil<U, F>(&self, radius: usize, f: F) -> GraphMat<U, C>
+    where
+        F: Fn(&NeighbourhoodView<'_, T, C>) -> Option<U>,
+    {
+        let side = 2 * radius + 1;
+        let offsets: Vec<[i32; 3]> = cube_offsets(radius).collect();
+        let mut view = NeighbourhoodView {
+            coord: [C::MIN; 3],
+            radius,
+            cells: vec![None; side * side * side],
+        };
+        let mut result = GraphMat::with_block_size(self.block_size());
+
+        for (leader_coord, leader) in self.map.iter() {
+            let leaders = NearbyLeaders::new(self, *leader_coord, radius);
+
+            let mut cells = RegionWalk {
+                min: *leader_coord,
+                max: self.block_end(*leader_coord),
+                ball: None,
+                leaders: vec![*leader].into_iter(),
+                stack: Vec::new(),
+            };
+            while let Some(idx) = cells.next_index(&self.arena) {
+                // SAFETY: next_index() only returns indices allocated in self.arena
+                view.coord = self.arena.get(idx).unwrap().coord;
+                for (cell, offset) in view.cells.iter_mut().zip(offsets.iter()) {
+                    *cell = leaders.get(self, view.coord, *offset);
+                }
+
+                if let Some(value) = f(&view) {
+                    result.set(view.coord, value);
+                }
+            }
+        }
+
+        result
+    }
+
+    /**
+     * @brief Weighted sum of the values around every cell having some data, with the weights of `kernel`
+     *
+     * The weight at offset [x, y, z] multiplies the value of the cell at coord + [x, y, z]
+     */
+    pub fn convolve(&self, kernel: &Kernel3D, boundary: Boundary) -> GraphMat<f64, C>
+    where
+        T: Copy + Into<f64>,
+    {
+        let total_weight: f64 = kernel.weights.iter().sum();
+
+        self.apply_stencil(kernel.radius, |view| {
+            let center: f64 = (*view.center()).into();
+            let (mut sum, mut used_weight) = (0.0, 0.0);
+
+            for (offset, weight) in cube_offsets(kernel.radius).zip(kernel.weights.iter()) {
+                let value = match (view.get(offset), boundary) {
+                    (Some(data), _) => (*data).into(),
+                    (None, Boundary::Zero) => 0.0,
+                    (None, Boundary::Clamp) => center,
+                    (None, Boundary::Renormalize) => continue,
+                };
+                sum += weight * value;
+                used_weight += weight;
+            }
+
+            if boundary == Boundary::Renormalize && used_weight != 0.0 {
+                sum *= total_weight / used_weight;
+            }
+
+            Some(sum)
+        })
+    }
+}
+
+// Leaders of the blocks around a block, looked up once so that the cells of the block can share them
+struct NearbyLeaders<C> {
+    first: [C; 3],
+    // Number of blocks along each axis
+    count: [usize; 3],
+    // Indexed like Kernel3D::weights, ie. [x][y][z] flattened with z varying fastest, None if not allocated
+    leaders: Vec<Option<IndexInArena>>,
+}
+
+impl<C: Coord> NearbyLeaders<C> {
+    fn new<T>(graphmat: &GraphMat<T, C>, leader_coord: [C; 3], radius: usize) -> Self {
+        let radius = radius as i64;
+        let first = graphmat.leader_coord(leader_coord.map(|c| c.offset(-radius).unwrap_or(C::MIN)));
+        let last = graphmat.leader_coord(graphmat.block_end(leader_coord).map(|c| c.offset(radius).unwrap_or(C::MAX)));
+
+        let block_size = graphmat.block_size() as i64;
+        let count = [0, 1, 2].map(|axis| ((last[axis].to_i64() - first[axis].to_i64()) / block_size) as usize + 1);
+
+        let mut leaders = Vec::with_capacity(count.iter().product());
+        for x in 0..count[0] {
+            for y in 0..count[1] {
+                for z in 0..count[2] {
+                    let blocks = [x, y, z];
+                    // SAFETY: Every leader between `first` and `last` is in range of C, since both of them are
+                    let leader = [0, 1, 2].map(|axis| first[axis].offset(blocks[axis] as i64 * block_size).unwrap());
+                    leaders.push(graphmat.map.get(&leader).copied());
+                }
+            }
+        }
+
+        NearbyLeaders { first, count, leaders }
+    }
+
+    // Value of the cell at coord + offset, found through the leaders looked up already
+    fn get<'a, T>(&self, graphmat: &'a GraphMat<T, C>, coord: [C; 3], offset: [i32; 3]) -> Option<&'a T> {
+        let pos = [coord[0].offset(offset[0] as i64)?, coord[1].offset(offset[1] as i64)?, coord[2].offset(offset[2] as i64)?];
+        let leader_coord = graphmat.leader_coord(pos);
+
+        let block_size = graphmat.block_size() as i64;
+        let mut index = 0;
+        for (axis, c) in leader_coord.iter().enumerate() {
+            let blocks = (c.to_i64() - self.first[axis].to_i64()) / block_size;
+            index = index * self.count[axis] + blocks as usize;
+        }
+
+        match graphmat.walk_from_leader(self.leaders[index]?, pos) {
+            // SAFETY: walk_from_leader() only returns indices allocated in the arena
+            Walk::Found(idx) => graphmat.arena.get(idx).unwrap().get(),
+            Walk::Missing { .. } => None,
+        }
+    }
+}
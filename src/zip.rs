@@ -0,0 +1,51 @@
+use crate::coord::Coord;
+use crate::graphmat::GraphMat;
+
+impl<T, C: Coord, const D: usize> GraphMat<T, C, D> {
+    /**
+     * @brief Combines the values of the cells having some data in both the matrices
+     *
+     * Cells having data in only one of them are left empty in the result
+     */
+    pub fn zip_with<U, V, F>(&self, other: &GraphMat<U, C, D>, mut f: F) -> GraphMat<V, C, D>
+    where
+        F: FnMut(&T, &U) -> V,
+    {
+        let mut result = GraphMat::with_block_size(self.block_size());
+        result.extend(self.iter().filter_map(|(coord, data)| other.get(coord).map(|other_data| (coord, f(data, other_data)))));
+
+        result
+    }
+
+    /**
+     * @brief Combines the values of the cells having some data in either of the matrices
+     *
+     * `f` is given None for the matrix not having data at the cell, it is never given None for both
+     */
+    pub fn zip_with_union<U, V, F>(&self, other: &GraphMat<U, C, D>, mut f: F) -> GraphMat<V, C, D>
+    where
+        F: FnMut(Option<&T>, Option<&U>) -> V,
+    {
+        let mut result = self.map_cells(|coord, data| f(Some(data), other.get(coord)));
+        result.extend(
+            other
+                .iter()
+                .filter(|(coord, _)| self.get(*coord).is_none())
+                .map(|(coord, other_data)| (coord, f(None, Some(other_data)))),
+        );
+
+        result
+    }
+
+    /**
+     * @brief Combines the value of every cell having some data in this matrix, with the value in `other` if any
+     *
+     * The result has data in exactly the same cells as this matrix, and is laid out the same as map() does
+     */
+    pub fn zip_with_left<U, V, F>(&self, other: &GraphMat<U, C, D>, mut f: F) -> GraphMat<V, C, D>
+    where
+        F: FnMut(&T, Option<&U>) -> V,
+    {
+        self.map_cells(|coord, data| f(data, other.get(coord)))
+    }
+}